@@ -0,0 +1,74 @@
+//! A sample bot for the external bot protocol described in
+//! `src/external_bot.rs`. It echoes back every frame number with a move
+//! towards the ball.
+//!
+//! Over stdin and stdout, started by the game:
+//!
+//!     cargo build --example echo_bot
+//!     cargo run -- --mode ai-vs-ai --external-bot echo=target/debug/examples/echo_bot --bots echo
+//!
+//! Or listening on a socket for the game to connect to, one game after
+//! another:
+//!
+//!     cargo run --example echo_bot -- --listen 127.0.0.1:7878
+//!     cargo run -- --mode ai-vs-ai --external-bot echo=tcp:127.0.0.1:7878 --bots echo
+
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::TcpListener,
+};
+
+use serde_json::{json, Value};
+
+const PROTOCOL_VERSION: u64 = 1;
+
+fn play(input: impl BufRead, mut output: impl Write) -> anyhow::Result<()> {
+    for line in input.lines() {
+        let message: Value = serde_json::from_str(&line?)?;
+        let reply = match message["type"].as_str() {
+            Some("hello") => {
+                let version = message["version"].as_u64().unwrap_or_default();
+                if version != PROTOCOL_VERSION {
+                    anyhow::bail!("the game speaks protocol version {}", version);
+                }
+                json!({ "type": "hello", "version": PROTOCOL_VERSION, "name": "echo" })
+            }
+            Some("observation") => {
+                let observation = &message["observation"];
+                let along = |point: &Value| {
+                    let rail = &observation["paddle"]["rail"];
+                    point[0].as_f64().unwrap_or_default() * rail[0].as_f64().unwrap_or_default()
+                        + point[1].as_f64().unwrap_or_default()
+                            * rail[1].as_f64().unwrap_or_default()
+                };
+                let diff = along(&observation["ball_position"])
+                    - along(&observation["paddle"]["position"]);
+                json!({ "type": "move", "frame": message["frame"], "command": diff.signum() })
+            }
+            other => anyhow::bail!("unexpected message {:?}", other),
+        };
+        writeln!(output, "{}", reply)?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.as_slice() {
+        [] => play(io::stdin().lock(), io::stdout().lock()),
+        [flag, address] if flag == "--listen" => {
+            let listener = TcpListener::bind(address)?;
+            loop {
+                eprintln!("waiting for the game on {}", address);
+                let (stream, peer) = listener.accept()?;
+                stream.set_nodelay(true)?;
+                // a game that goes wrong doesn't stop the bot from playing the next
+                if let Err(err) = play(BufReader::new(stream.try_clone()?), stream) {
+                    eprintln!("game at {} failed: {:#}", peer, err);
+                }
+            }
+        }
+        _ => anyhow::bail!("usage: echo_bot [--listen <address>]"),
+    }
+}
@@ -0,0 +1,55 @@
+//! Plays whole episodes in parallel environments with a simple policy that
+//! chases the ball, and prints the reward of each and how fast they ran. A
+//! starting point for plugging in a policy that learns.
+//!
+//!     cargo run --release --example vec_env -- 16
+
+use std::time::Instant;
+
+use bevy_pong::{
+    bot::{MoveCommand, Observation},
+    env::{Action, EnvConfig, VecEnv},
+};
+
+fn policy(observation: &Observation) -> Action {
+    let paddle = &observation.paddle;
+    let diff = (observation.ball_position - paddle.position).dot(paddle.rail);
+    MoveCommand(diff.clamp(-1., 1.))
+}
+
+fn main() -> anyhow::Result<()> {
+    let count = match std::env::args().nth(1) {
+        Some(count) => count.parse()?,
+        None => 8,
+    };
+    let mut envs = VecEnv::new(count, &EnvConfig::default())?;
+    let mut observations = envs.reset(0);
+    let mut returns = vec![0.; count];
+    let mut running = vec![true; count];
+    let mut steps = 0;
+
+    let start = Instant::now();
+    while running.iter().any(|running| *running) {
+        let actions: Vec<Action> = observations.iter().map(policy).collect();
+        for (index, (observation, reward, done)) in envs.step(&actions).into_iter().enumerate() {
+            observations[index] = observation;
+            if running[index] {
+                returns[index] += reward;
+                steps += 1;
+                running[index] = !done;
+            }
+        }
+    }
+    let elapsed = start.elapsed().as_secs_f32();
+
+    for (index, episode_return) in returns.iter().enumerate() {
+        println!("environment {}: return {:.1}", index, episode_return);
+    }
+    println!(
+        "{} steps in {:.1}s, {:.0} steps per second",
+        steps,
+        elapsed,
+        steps as f32 / elapsed
+    );
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use bevy::{
+    prelude::*,
+    render::camera::{ScalingMode, Viewport},
+};
+
+use crate::utils::ARENA_SIZE;
+
+fn spawn_camera(mut commands: Commands) {
+    let mut camera = Camera2dBundle::default();
+    camera.projection.scaling_mode = ScalingMode::Fixed {
+        width: ARENA_SIZE.x,
+        height: ARENA_SIZE.y,
+    };
+    commands.spawn(camera);
+}
+
+/// Renders to the largest part of the window with the aspect ratio of the
+/// arena, centered, leaving black bars on the sides that are too long.
+fn fit_viewport(windows: Query<&Window, Changed<Window>>, mut cameras: Query<&mut Camera>) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let window_size = Vec2::new(
+        window.physical_width() as f32,
+        window.physical_height() as f32,
+    );
+    // minimized
+    if window_size.min_element() < 1. {
+        return;
+    }
+
+    let scale = (window_size / ARENA_SIZE).min_element();
+    let size = (ARENA_SIZE * scale).round().max(Vec2::ONE);
+    let position = ((window_size - size) / 2.).floor();
+    for mut camera in &mut cameras {
+        camera.viewport = Some(Viewport {
+            physical_position: position.as_uvec2(),
+            physical_size: size.as_uvec2(),
+            ..default()
+        });
+    }
+}
+
+/// Shows the fixed size arena scaled to fit the window, so the court and
+/// everything on it is the same on every display.
+pub struct ArenaPlugin;
+impl Plugin for ArenaPlugin {
+    fn build(&self, app: &mut App) {
+        // the bars around the arena
+        app.insert_resource(ClearColor(Color::BLACK))
+            .add_systems(Startup, spawn_camera)
+            .add_systems(Update, fit_viewport);
+    }
+}
@@ -2,203 +2,562 @@ use bevy::{
     ecs::entity::Entity,
     math::bounding::{Aabb2d, BoundingCircle},
     prelude::*,
-    sprite::MaterialMesh2dBundle,
 };
 
 use crate::{
-    game_manager::{countdown_guard, AllowedToRun, Scored},
-    paddle::Paddle,
+    border::Border,
+    console::{ConsoleCommand, RegisterCommand},
+    game_manager::{
+        countdown_guard, countdown_serves, AllowedToRun, GameState, GameplaySet, MatchSetup, Score,
+        Scored,
+    },
+    paddle::{Paddle, Rail},
+    rng::GameRng,
+    settings::{BallSpeed, Settings},
     spritesheet_animation::{AnimationIndices, AnimationTimer},
-    utils::{ball_collision, project_positions, Collision, Position, Shape, Velocity},
+    team::Teams,
+    time_scale::TimeScale,
+    utils::{
+        asset_path, ball_collision, load_ron_or_default, project_positions, Contact, Position,
+        Shape, Velocity,
+    },
 };
-use anyhow::Result;
+use anyhow::{bail, Result};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
 
-const INITIAL_SPEED: f32 = 6.0;
 const RADIUS: f32 = 20.0;
+const TUNING_PATH: &str = "config/ball.ron";
+
+/// How the ball speeds up every time a paddle hits it.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub enum SpeedRamp {
+    /// Adds a fixed amount to the speed.
+    Additive(f32),
+    /// Multiplies the speed by a factor.
+    Multiplicative(f32),
+}
+
+impl SpeedRamp {
+    fn apply(&self, speed: f32) -> f32 {
+        match self {
+            SpeedRamp::Additive(amount) => speed + amount,
+            SpeedRamp::Multiplicative(factor) => speed * factor,
+        }
+    }
+}
+
+/// Ball speeds, in units per frame. Loaded from `assets/config/ball.ron`.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct BallTuning {
+    pub initial_speed: f32,
+    pub acceleration: SpeedRamp,
+    pub max_speed: f32,
+    pub reset_speed: f32,
+    /// Share of the paddle's vertical velocity passed on to the ball on a hit.
+    pub paddle_transfer: f32,
+    /// Limits of the serve angle, in degrees from the horizontal.
+    pub serve_min_angle: f32,
+    pub serve_max_angle: f32,
+    /// Spin, in radians per frame, given per unit of paddle velocity on a hit.
+    pub spin_transfer: f32,
+    /// How strongly spin curves the flight: the velocity turns by
+    /// `magnus * spin` radians every frame.
+    pub magnus: f32,
+    /// Share of the spin kept from one frame to the next.
+    pub spin_decay: f32,
+    /// Sideways kick per unit of spin when bouncing off the top or bottom.
+    pub spin_bounce: f32,
+}
+
+impl Default for BallTuning {
+    fn default() -> Self {
+        BallTuning {
+            initial_speed: 8.5,
+            acceleration: SpeedRamp::Additive(1.4),
+            max_speed: 20.,
+            reset_speed: 8.5,
+            paddle_transfer: 0.3,
+            serve_min_angle: 20.,
+            serve_max_angle: 60.,
+            spin_transfer: 0.02,
+            magnus: 0.05,
+            spin_decay: 0.99,
+            spin_bounce: 15.,
+        }
+    }
+}
+
+impl BallTuning {
+    /// Rejects tunings that would stall, slow down or never accelerate the ball.
+    fn validate(&self) -> Result<()> {
+        match self.acceleration {
+            SpeedRamp::Additive(amount) if amount < 0. => {
+                bail!("additive acceleration {amount} is negative")
+            }
+            SpeedRamp::Multiplicative(factor) if factor < 1. => {
+                bail!("multiplicative acceleration {factor} is below 1")
+            }
+            _ => {}
+        }
+        if self.max_speed <= 0. {
+            bail!("max_speed {} is not positive", self.max_speed);
+        }
+        if self.initial_speed > self.max_speed || self.reset_speed > self.max_speed {
+            bail!(
+                "initial_speed {} and reset_speed {} must not exceed max_speed {}",
+                self.initial_speed,
+                self.reset_speed,
+                self.max_speed
+            );
+        }
+        Ok(())
+    }
+
+    /// These speeds scaled for the `speed` preset.
+    fn with_speed(&self, speed: BallSpeed) -> Self {
+        let factor = speed.factor();
+        BallTuning {
+            initial_speed: self.initial_speed * factor,
+            acceleration: match self.acceleration {
+                SpeedRamp::Additive(amount) => SpeedRamp::Additive(amount * factor),
+                ramp @ SpeedRamp::Multiplicative(_) => ramp,
+            },
+            max_speed: self.max_speed * factor,
+            reset_speed: self.reset_speed * factor,
+            ..self.clone()
+        }
+    }
+
+    /// A serve going right for a positive `horizontal` and left otherwise, at a random
+    /// angle within the limits, up or down.
+    pub fn serve_velocity(&self, rng: &mut GameRng, horizontal: f32, speed: f32) -> Vec2 {
+        let low = self.serve_min_angle.min(self.serve_max_angle);
+        let high = self.serve_min_angle.max(self.serve_max_angle);
+        let angle = rng.gen_range(low..=high).to_radians();
+        let vertical = if rng.gen_bool(0.5) { 1. } else { -1. };
+        Vec2::new(horizontal.signum() * angle.cos(), vertical * angle.sin()) * speed
+    }
+
+    /// Like `serve_velocity`, with the angle measured from `direction` instead
+    /// of the horizontal.
+    pub fn serve_towards(&self, rng: &mut GameRng, direction: Vec2, speed: f32) -> Vec2 {
+        let serve = self.serve_velocity(rng, 1., speed);
+        direction.normalize_or_zero().rotate(serve)
+    }
+}
 
 #[derive(Component)]
 pub struct Ball;
 
 #[derive(Event)]
 pub struct BallCollision {
-    pub collision: Collision,
+    pub contact: Contact,
     pub entity: Entity,
 }
 
+/// Angular velocity of the ball in radians per frame, counterclockwise
+/// positive, and how far it has turned so far.
+#[derive(Component, Default)]
+pub struct Spin {
+    pub rate: f32,
+    pub angle: f32,
+}
+
+/// Entities the ball was touching on the previous frame, so a contact
+/// that lasts several frames is only reported once.
+#[derive(Component, Default)]
+pub struct Contacts(Vec<Entity>);
+
 #[derive(Bundle)]
 struct BallBundle {
     ball: Ball,
     position: Position,
     velocity: Velocity,
     shape: Shape,
+    spin: Spin,
+    contacts: Contacts,
 }
 
 impl BallBundle {
-    fn new() -> Self {
+    fn new(velocity: Vec2) -> Self {
         BallBundle {
             ball: Ball,
             shape: Shape::Circle { radius: RADIUS },
-            velocity: Velocity(Vec2::new(INITIAL_SPEED, INITIAL_SPEED)),
+            velocity: Velocity(velocity),
             position: Position(Vec2::new(0., 0.)),
+            spin: Spin::default(),
+            contacts: Contacts::default(),
         }
     }
 }
 
-fn spawn_ball(
+/// The tuning as loaded, before the speed preset of the settings is applied.
+#[derive(Resource)]
+struct LoadedTuning(BallTuning);
+
+fn apply_speed_preset(
+    mut tuning: ResMut<BallTuning>,
+    loaded: Res<LoadedTuning>,
+    settings: Res<Settings>,
+) {
+    *tuning = loaded.0.with_speed(settings.ball_speed);
+}
+
+/// The first serve of a match goes left or right at random.
+fn opening_serve(tuning: &BallTuning, rng: &mut GameRng) -> Vec2 {
+    let horizontal = if rng.gen_bool(0.5) { 1. } else { -1. };
+    tuning.serve_velocity(rng, horizontal, tuning.initial_speed)
+}
+
+/// The ball stays still until the first match starts and serves it.
+fn spawn_ball(mut commands: Commands) {
+    commands.spawn(BallBundle::new(Vec2::ZERO));
+}
+
+/// Puts the ball back in the middle for a new match, which may have been
+/// restarted or quit with the ball anywhere on the court.
+pub fn serve_new_match(
+    mut ball: Query<(&mut Position, &mut Velocity, &mut Spin), With<Ball>>,
+    tuning: Res<BallTuning>,
+    mut rng: ResMut<GameRng>,
+) {
+    let Ok((mut position, mut velocity, mut spin)) = ball.get_single_mut() else {
+        return;
+    };
+    position.0 = Vec2::ZERO;
+    spin.rate = 0.;
+    velocity.0 = opening_serve(&tuning, &mut rng);
+}
+
+fn attach_sprite(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    ball: Query<Entity, Added<Ball>>,
     asset_server: Res<AssetServer>,
     mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
 ) {
-    let shape = Mesh::from(Circle::new(RADIUS));
-    let color = ColorMaterial::from(Color::rgb(1., 0., 0.));
-
-    let texture = asset_server.load("fireball.png");
-
-    let layout = TextureAtlasLayout::from_grid(Vec2::new(71.3, 45.6), 3, 3, None, None);
-    let texture_atlas_layout = texture_atlas_layouts.add(layout);
-    // Use only the subset of sprites in the sheet that make up the run animation
-    let animation_indices = AnimationIndices { first: 1, last: 8 };
-
-    commands.spawn((
-        BallBundle::new(),
-        // MaterialMesh2dBundle {
-        //     mesh: mesh_handle.into(),
-        //     material: color_handle,
-        //     ..default()
-        // },
-        SpriteSheetBundle {
-            texture,
-            atlas: TextureAtlas {
-                layout: texture_atlas_layout,
-                index: animation_indices.first,
+    for entity in &ball {
+        let texture = asset_server.load("fireball.png");
+
+        let layout = TextureAtlasLayout::from_grid(Vec2::new(71.3, 45.6), 3, 3, None, None);
+        let texture_atlas_layout = texture_atlas_layouts.add(layout);
+        // Use only the subset of sprites in the sheet that make up the run animation
+        let animation_indices = AnimationIndices { first: 1, last: 8 };
+
+        commands.entity(entity).insert((
+            SpriteSheetBundle {
+                texture,
+                atlas: TextureAtlas {
+                    layout: texture_atlas_layout,
+                    index: animation_indices.first,
+                },
+                //transform: Transform::from_scale(Vec3::splat(6.0)),
+                ..default()
             },
-            //transform: Transform::from_scale(Vec3::splat(6.0)),
-            ..default()
-        },
-        animation_indices,
-        AnimationTimer(Timer::from_seconds(0.1, TimerMode::Repeating)),
-    ));
+            animation_indices,
+            AnimationTimer(Timer::from_seconds(0.1, TimerMode::Repeating)),
+        ));
+    }
 }
 
 fn move_ball(
     In(allowed): In<AllowedToRun>,
     mut ball: Query<(&mut Position, &Velocity), With<Ball>>,
+    time_scale: Res<TimeScale>,
 ) {
     if !allowed {
         return;
     }
 
     if let Ok((mut position, velocity)) = ball.get_single_mut() {
-        position.0 += velocity.0
+        position.0 += velocity.0 * time_scale.factor()
+    }
+}
+
+/// Turns the velocity sideways according to the spin (the Magnus effect),
+/// keeping the speed, and lets the spin die down.
+fn curve_ball(
+    In(allowed): In<AllowedToRun>,
+    mut ball: Query<(&mut Velocity, &mut Spin), With<Ball>>,
+    tuning: Res<BallTuning>,
+    time_scale: Res<TimeScale>,
+) {
+    if !allowed {
+        return;
+    }
+
+    if let Ok((mut velocity, mut spin)) = ball.get_single_mut() {
+        let factor = time_scale.factor();
+        velocity.0 = Vec2::from_angle(tuning.magnus * spin.rate * factor).rotate(velocity.0);
+        spin.angle += spin.rate * factor;
+        spin.rate *= tuning.spin_decay.powf(factor);
     }
 }
 
 fn collision(
-    mut ball: Query<(&mut Velocity, &Position, &Shape), With<Ball>>,
+    mut ball: Query<(&mut Velocity, &mut Position, &Shape, &mut Contacts), With<Ball>>,
     world: Query<(&Position, &Shape, Entity), Without<Ball>>,
     mut events: EventWriter<BallCollision>,
 ) {
-    if let Ok((mut ball_velocity, ball_position, Shape::Circle { radius })) = ball.get_single_mut()
+    if let Ok((mut ball_velocity, mut ball_position, Shape::Circle { radius }, mut contacts)) =
+        ball.get_single_mut()
     {
+        let mut touching = Vec::new();
         for (position, shape, entity) in &world {
             if let Shape::Rectangle { width, height } = shape {
-                if let Some(collision) = ball_collision(
-                    BoundingCircle::new(ball_position.0, radius.clone()),
-                    Aabb2d::new(position.0, Vec2::new(width.clone(), height.clone()) / 2.0),
+                if let Some(contact) = ball_collision(
+                    BoundingCircle::new(ball_position.0, *radius),
+                    Aabb2d::new(position.0, Vec2::new(*width, *height) / 2.0),
                 ) {
-                    events.send(BallCollision { collision, entity });
-                    match collision {
-                        crate::utils::Collision::Left => ball_velocity.0.x *= -1.,
-                        crate::utils::Collision::Right => ball_velocity.0.x *= -1.,
-                        crate::utils::Collision::Top => ball_velocity.0.y *= -1.,
-                        crate::utils::Collision::Bottom => ball_velocity.0.y *= -1.,
+                    // push the ball out of the wall, then reflect it about the normal
+                    // unless it is already moving away
+                    ball_position.0 += contact.normal * contact.penetration;
+                    let approach = ball_velocity.0.dot(contact.normal);
+                    if approach < 0. {
+                        ball_velocity.0 -= 2. * approach * contact.normal;
+                    }
+
+                    if !contacts.0.contains(&entity) {
+                        events.send(BallCollision { contact, entity });
                     }
+                    touching.push(entity);
                 }
             }
         }
+        contacts.0 = touching;
     }
 }
 
+/// Puts the ball back in the middle after a goal and serves it towards one of
+/// the teams still in the match other than the one that let the goal in.
 fn reset_on_score(
-    mut ball: Query<(&mut Position, &mut Velocity), With<Ball>>,
+    mut ball: Query<(&mut Position, &mut Velocity, &mut Spin), With<Ball>>,
     mut events: EventReader<Scored>,
+    score: Res<Score>,
+    teams: Res<Teams>,
+    setup: Res<MatchSetup>,
+    tuning: Res<BallTuning>,
+    mut rng: ResMut<GameRng>,
 ) {
-    _ = || -> Result<()> {
-        let (mut position, mut velocity) = ball.get_single_mut()?;
-        for event in events.read() {
-            position.0 = Vec2::new(0., 0.);
-            velocity.0.y = INITIAL_SPEED;
-            match event {
-                Scored::Player => velocity.0.x = 1. * INITIAL_SPEED,
-                Scored::Ai => velocity.0.x = -1. * INITIAL_SPEED,
-            };
-        }
-        Ok(())
-    }();
+    let Ok((mut position, mut velocity, mut spin)) = ball.get_single_mut() else {
+        return;
+    };
+    for event in events.read() {
+        let targets: Vec<Border> = score
+            .in_play(&teams, setup.target_score)
+            .into_iter()
+            .filter(|team| *team != event.against)
+            .flat_map(|team| teams.get(team).goals.clone())
+            .collect();
+        let side = match targets[..] {
+            // nobody else is left to serve to, as in practice
+            [] => match teams.get(event.against).goals[..] {
+                [] => continue,
+                [side, ..] => side,
+            },
+            [side] => side,
+            _ => targets[rng.gen_range(0..targets.len())],
+        };
+
+        position.0 = Vec2::new(0., 0.);
+        spin.rate = 0.;
+        velocity.0 = tuning.serve_towards(&mut rng, side.outward(), tuning.reset_speed);
+    }
 }
 
 fn adjust_sprite_flip_rotation(
-    mut ball: Query<(&mut Sprite, &mut Transform, &Velocity), With<Ball>>,
+    mut ball: Query<(&mut Sprite, &mut Transform, &Velocity, &Spin), With<Ball>>,
 ) {
     _ = || -> Result<()> {
-        let (mut sprite, mut transform, velocity) = ball.get_single_mut()?;
-        if velocity.0.x > 0. {
-            sprite.flip_x = true;
-        } else {
-            sprite.flip_x = false;
-        }
-
-        let flip_modifier: f32;
-        if sprite.flip_x {
-            flip_modifier = 1.0;
-        } else {
-            flip_modifier = -1.0;
-        }
-
-        let angle_modifier: f32;
-        if velocity.0.y > 0.0 {
-            angle_modifier = 1.;
-        } else {
-            angle_modifier = -1.;
-        }
+        let (mut sprite, mut transform, velocity, spin) = ball.get_single_mut()?;
+        sprite.flip_x = velocity.0.x > 0.;
 
-        let angle = flip_modifier * angle_modifier * 45.0;
-        *transform = transform.with_rotation(Quat::from_rotation_z(angle.to_radians()));
+        // the sprite faces left, or right when flipped; point it along the flight
+        // and turn it further by however much the ball has spun
+        let heading = velocity.0.y.atan2(velocity.0.x);
+        let facing = if sprite.flip_x { 0. } else { PI };
+        let angle = heading - facing + spin.angle;
+        *transform = transform.with_rotation(Quat::from_rotation_z(angle));
         Ok(())
     }();
 }
 
-fn increase_speed_on_collision(
-    mut ball: Query<&mut Velocity, With<Ball>>,
-    paddles: Query<&Paddle>,
+fn paddle_hit(
+    mut ball: Query<(&mut Velocity, &mut Spin), With<Ball>>,
+    paddles: Query<(&Velocity, &Rail), (With<Paddle>, Without<Ball>)>,
+    mut events: EventReader<BallCollision>,
+    tuning: Res<BallTuning>,
+) {
+    for event in events.read() {
+        let Ok((paddle_velocity, rail)) = paddles.get(event.entity) else {
+            continue;
+        };
+        // only hits on the face of the paddle count, not grazing its ends
+        if rail.is_face(event.contact.side) {
+            let (mut velocity, mut spin) = ball.single_mut();
+            // a moving paddle drags the ball along and sets it spinning, the
+            // direction depending on which side of the ball it rubs
+            velocity.0 += paddle_velocity.0 * tuning.paddle_transfer;
+            spin.rate -= event.contact.normal.perp_dot(paddle_velocity.0) * tuning.spin_transfer;
+            // then scale the magnitude only, so the angle of the shot is kept
+            let speed = velocity.0.length();
+            let new_speed = tuning.acceleration.apply(speed).min(tuning.max_speed);
+            velocity.0 = velocity.0.normalize_or_zero() * new_speed;
+        }
+    }
+}
+
+/// A spinning ball grips the top and bottom walls: the surface rubbing the wall
+/// pushes the ball along it, using up part of the spin.
+fn spin_bounce(
+    mut ball: Query<(&mut Velocity, &mut Spin), With<Ball>>,
+    borders: Query<&Border>,
     mut events: EventReader<BallCollision>,
+    tuning: Res<BallTuning>,
 ) {
     for event in events.read() {
-        if paddles.contains(event.entity) {
-            let mut velocity = ball.single_mut();
-            velocity.0.x += if velocity.0.x > 0. { 1.0 } else { -1.0 };
-            velocity.0.y += if velocity.0.y > 0. { 1.0 } else { -1.0 };
+        if !matches!(borders.get(event.entity), Ok(Border::Top | Border::Bottom)) {
+            continue;
         }
+        let Ok((mut velocity, mut spin)) = ball.get_single_mut() else {
+            continue;
+        };
+        let normal = event.contact.normal;
+        velocity.0 -= spin.rate * tuning.spin_bounce * Vec2::new(normal.y, -normal.x);
+        spin.rate /= 2.;
+    }
+}
+
+/// `set ball.speed <speed>` changes how fast the ball flies right now,
+/// `set ball.max_speed <speed>` how fast paddle hits can make it.
+struct SetCommand;
+impl ConsoleCommand for SetCommand {
+    fn name(&self) -> &'static str {
+        "set"
+    }
+
+    fn usage(&self) -> &'static str {
+        "set ball.speed|ball.max_speed <units per frame>"
+    }
+
+    fn run(&self, args: &[&str], world: &mut World) -> Result<String> {
+        let [key, value] = args else {
+            bail!("usage: {}", self.usage());
+        };
+        let value: f32 = value.parse()?;
+        match *key {
+            "ball.speed" => {
+                let mut ball = world.query_filtered::<&mut Velocity, With<Ball>>();
+                let mut velocity = ball.get_single_mut(world)?;
+                if velocity.0 == Vec2::ZERO {
+                    bail!("the ball is not moving");
+                }
+                velocity.0 = velocity.0.normalize() * value;
+            }
+            "ball.max_speed" => world.resource_mut::<BallTuning>().max_speed = value,
+            _ => bail!("unknown setting `{}`", key),
+        }
+        Ok(format!("{} = {}", key, value))
+    }
+}
+
+/// `spawn ball` puts a new ball in the middle and serves it. There is only
+/// ever one ball, so it takes the place of the one in play.
+struct SpawnCommand;
+impl ConsoleCommand for SpawnCommand {
+    fn name(&self) -> &'static str {
+        "spawn"
+    }
+
+    fn usage(&self) -> &'static str {
+        "spawn ball"
+    }
+
+    fn run(&self, args: &[&str], world: &mut World) -> Result<String> {
+        if args != ["ball"] {
+            bail!("usage: {}", self.usage());
+        }
+        let mut balls = world.query_filtered::<Entity, With<Ball>>();
+        for entity in balls.iter(world).collect::<Vec<_>>() {
+            world.entity_mut(entity).despawn_recursive();
+        }
+        let velocity = world.resource_scope(|world, mut rng: Mut<GameRng>| {
+            opening_serve(world.resource::<BallTuning>(), &mut rng)
+        });
+        world.spawn(BallBundle::new(velocity));
+        Ok("served a new ball".to_string())
     }
 }
 
 pub struct BallPlugin;
 impl Plugin for BallPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, spawn_ball)
+        let path = asset_path(TUNING_PATH);
+        let mut tuning: BallTuning = load_ron_or_default(&path);
+        if let Err(err) = tuning.validate() {
+            warn!("using defaults, invalid {}: {}", path.display(), err);
+            tuning = BallTuning::default();
+        }
+        app.insert_resource(tuning.clone())
+            .insert_resource(LoadedTuning(tuning))
+            .add_systems(Startup, spawn_ball)
+            .add_systems(
+                OnEnter(GameState::Playing),
+                serve_new_match.run_if(countdown_serves),
+            )
             .add_systems(
                 Update,
                 (
+                    attach_sprite.run_if(resource_exists::<Assets<TextureAtlasLayout>>),
+                    apply_speed_preset.run_if(resource_changed::<Settings>),
                     adjust_sprite_flip_rotation,
-                    reset_on_score,
-                    increase_speed_on_collision,
-                    countdown_guard.pipe(move_ball),
+                    (
+                        reset_on_score.run_if(countdown_serves),
+                        countdown_guard.pipe(curve_ball).before(move_ball),
+                        countdown_guard.pipe(move_ball),
+                        collision.after(move_ball),
+                        // react to this frame's contacts rather than last frame's
+                        (paddle_hit, spin_bounce).chain().after(collision),
+                    )
+                        .in_set(GameplaySet),
                     project_positions.after(move_ball),
-                    collision.after(move_ball),
                 ),
             )
-            .add_event::<BallCollision>();
+            .add_event::<BallCollision>()
+            .register_command(SetCommand)
+            .register_command(SpawnCommand);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_rejects_bad_tunings() {
+        assert!(BallTuning::default().validate().is_ok());
+        let bad = [
+            BallTuning {
+                acceleration: SpeedRamp::Additive(-1.),
+                ..default()
+            },
+            BallTuning {
+                acceleration: SpeedRamp::Multiplicative(0.9),
+                ..default()
+            },
+            BallTuning {
+                initial_speed: 30.,
+                ..default()
+            },
+            BallTuning {
+                reset_speed: 30.,
+                ..default()
+            },
+        ];
+        for tuning in bad {
+            assert!(tuning.validate().is_err(), "{tuning:?}");
+        }
     }
 }
@@ -0,0 +1,552 @@
+//! Plays paddle bots against each other without a window, as fast as the
+//! simulation runs, and ranks them.
+//!
+//!     cargo run --release --bin tournament -- --bots follow,predict --matches 10
+//!     cargo run --release --bin tournament -- --format swiss --rounds 4 \
+//!         --bots follow,predict,echo --external-bot echo=target/release/examples/echo_bot
+
+use std::{path::PathBuf, process::ExitCode, time::Duration};
+
+use anyhow::{bail, Context};
+use bevy::{ecs::event::ManualEventReader, prelude::*};
+use bevy_pong::{
+    add_game_plugins,
+    border::Border,
+    bot::BotRegistry,
+    cli::external_bot,
+    external_bot::register_external_bots,
+    game_manager::{GameMode, GameState, MatchFinished, MatchSetup, Score},
+    headless::HeadlessPlugin,
+    level::Level,
+    rng::GameRng,
+    settings::{Difficulty, Settings, SettingsPlugin},
+    stats::MatchStats,
+    team::Teams,
+};
+use clap::{Parser, ValueEnum};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// Rating every bot starts the tournament with.
+const INITIAL_ELO: f32 = 1500.;
+/// How far a single match moves the ratings.
+const ELO_K: f32 = 32.;
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq, Debug)]
+enum Format {
+    /// Every bot plays every other bot.
+    RoundRobin,
+    /// Bots play others with a similar score each round, never the same one twice.
+    Swiss,
+}
+
+/// Runs a tournament between paddle bots.
+#[derive(Parser, Debug)]
+#[command(version, about = "Bot tournaments for Bevy Pong")]
+struct Args {
+    /// Bots taking part, e.g. `follow,predict`.
+    #[arg(long, value_delimiter = ',', num_args = 1.., required = true)]
+    bots: Vec<String>,
+
+    #[arg(long, value_enum, default_value_t = Format::RoundRobin)]
+    format: Format,
+
+    /// Matches every pairing plays, the bots swapping sides after each one.
+    #[arg(long, default_value_t = 2, value_parser = clap::value_parser!(u32).range(1..))]
+    matches: u32,
+
+    /// Rounds of a Swiss tournament, by default enough to find a clear winner.
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    rounds: Option<u32>,
+
+    /// Seed the serves of every match are drawn from, to reproduce a tournament.
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Points needed to win a match.
+    #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u32).range(1..))]
+    target_score: u32,
+
+    /// How fast the bots may move their paddles.
+    #[arg(long, value_enum, default_value_t = Difficulty::Normal)]
+    difficulty: Difficulty,
+
+    /// Level file with extra obstacles.
+    #[arg(long, value_name = "FILE")]
+    level: Option<PathBuf>,
+
+    /// Adds a bot run by another program, see the game's `--external-bot`.
+    #[arg(long, value_name = "NAME=COMMAND", value_parser = external_bot)]
+    external_bot: Vec<(String, String)>,
+
+    /// How long external bots have to answer every frame.
+    #[arg(long, value_name = "MS", default_value_t = 8)]
+    bot_timeout_ms: u64,
+
+    /// Frames after which a match is called a draw, ten minutes by default.
+    #[arg(long, default_value_t = 36_000)]
+    max_frames: u32,
+}
+
+/// How a match ended for the bot on the right.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Outcome {
+    Win,
+    Loss,
+    Draw,
+}
+
+impl Outcome {
+    /// Points of the bot this is the outcome for.
+    fn points(self) -> f32 {
+        match self {
+            Outcome::Win => 1.,
+            Outcome::Loss => 0.,
+            Outcome::Draw => 0.5,
+        }
+    }
+
+    fn reversed(self) -> Self {
+        match self {
+            Outcome::Win => Outcome::Loss,
+            Outcome::Loss => Outcome::Win,
+            Outcome::Draw => Outcome::Draw,
+        }
+    }
+}
+
+struct MatchResult {
+    outcome: Outcome,
+    /// Final score of the right and left bots.
+    score: (u32, u32),
+    rallies: Vec<u32>,
+    frames: u32,
+}
+
+/// The game, run one match at a time between any two bots.
+struct Simulation {
+    app: App,
+    finished: ManualEventReader<MatchFinished>,
+    max_frames: u32,
+}
+
+impl Simulation {
+    fn new(args: &Args, level: Level) -> anyhow::Result<Self> {
+        let settings = Settings {
+            difficulty: args.difficulty,
+            target_score: args.target_score,
+            ..default()
+        };
+        let setup = MatchSetup::from_settings(&settings).with_mode(GameMode::AiVsAi);
+
+        let mut app = App::new();
+        // wait in the menu until the first match is set up
+        app.add_plugins(HeadlessPlugin)
+            .insert_state(GameState::Menu);
+        add_game_plugins(&mut app, SettingsPlugin::new(settings), setup, level);
+        let timeout = Duration::from_millis(args.bot_timeout_ms);
+        register_external_bots(&mut app, &args.external_bot, timeout);
+
+        app.world
+            .resource::<BotRegistry>()
+            .check_names(&args.bots)?;
+
+        app.finish();
+        app.cleanup();
+        app.update();
+        Ok(Simulation {
+            app,
+            finished: ManualEventReader::default(),
+            max_frames: args.max_frames,
+        })
+    }
+
+    /// Plays a match of `right` against `left`, served from `seed`.
+    fn play(&mut self, right: &str, left: &str, seed: u64) -> MatchResult {
+        let world = &mut self.app.world;
+        let mut setup = world.resource_mut::<MatchSetup>();
+        setup.bots[0] = right.to_string();
+        setup.bots[1] = left.to_string();
+        setup.seed = seed;
+        world.insert_resource(GameRng::new(seed));
+        world
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::Playing);
+
+        let mut frames = 0;
+        let finished = loop {
+            self.app.update();
+            frames += 1;
+            let events = self.app.world.resource::<Events<MatchFinished>>();
+            if let Some(event) = self.finished.read(events).last() {
+                break Some((event.final_score.clone(), event.winner));
+            }
+            if frames >= self.max_frames {
+                break None;
+            }
+        };
+
+        let world = &mut self.app.world;
+        let teams = world.resource::<Teams>();
+        let side = |border| teams.owner(border).expect("both sides have a team");
+        let (right_team, left_team) = (side(Border::Right), side(Border::Left));
+        let (score, outcome) = match &finished {
+            Some((score, winner)) => (
+                score,
+                if *winner == right_team {
+                    Outcome::Win
+                } else {
+                    Outcome::Loss
+                },
+            ),
+            None => (world.resource::<Score>(), Outcome::Draw),
+        };
+        let score = (score.points(right_team), score.points(left_team));
+        let rallies = world.resource::<MatchStats>().rallies.clone();
+
+        // back to the menu, which takes the paddles and their bots off the court
+        world
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::Menu);
+        self.app.update();
+
+        MatchResult {
+            outcome,
+            score,
+            rallies,
+            frames,
+        }
+    }
+}
+
+/// How a bot is doing in the tournament.
+struct Standing {
+    name: String,
+    wins: u32,
+    losses: u32,
+    draws: u32,
+    /// Points from byes in a Swiss tournament, on top of the matches.
+    byes: u32,
+    elo: f32,
+    rallies: Vec<u32>,
+    /// Indices of the bots already played.
+    opponents: Vec<usize>,
+}
+
+impl Standing {
+    fn new(name: &str) -> Self {
+        Standing {
+            name: name.to_string(),
+            wins: 0,
+            losses: 0,
+            draws: 0,
+            byes: 0,
+            elo: INITIAL_ELO,
+            rallies: Vec::new(),
+            opponents: Vec::new(),
+        }
+    }
+
+    fn played(&self) -> u32 {
+        self.wins + self.losses + self.draws
+    }
+
+    /// Wins count one point, draws half of one.
+    fn points(&self) -> f32 {
+        self.wins as f32 + self.draws as f32 / 2. + self.byes as f32
+    }
+
+    fn win_rate(&self) -> f32 {
+        match self.played() {
+            0 => 0.,
+            played => (self.wins as f32 + self.draws as f32 / 2.) / played as f32,
+        }
+    }
+
+    fn average_rally(&self) -> f32 {
+        match self.rallies.len() {
+            0 => 0.,
+            count => self.rallies.iter().sum::<u32>() as f32 / count as f32,
+        }
+    }
+
+    fn record(&mut self, outcome: Outcome, rallies: &[u32]) {
+        match outcome {
+            Outcome::Win => self.wins += 1,
+            Outcome::Loss => self.losses += 1,
+            Outcome::Draw => self.draws += 1,
+        }
+        self.rallies.extend_from_slice(rallies);
+    }
+}
+
+/// Chance of a bot rated `rating` beating one rated `opponent`.
+fn expected_score(rating: f32, opponent: f32) -> f32 {
+    1. / (1. + 10f32.powf((opponent - rating) / 400.))
+}
+
+/// Pairs the bots for the next round of a Swiss tournament. Returns the bot
+/// that sits the round out, if their number is odd, and the pairings.
+fn swiss_pairings(standings: &[Standing]) -> (Option<usize>, Vec<(usize, usize)>) {
+    let mut unpaired: Vec<usize> = (0..standings.len()).collect();
+    unpaired.sort_by(|a, b| {
+        let (a, b) = (&standings[*a], &standings[*b]);
+        b.points()
+            .total_cmp(&a.points())
+            .then(b.elo.total_cmp(&a.elo))
+    });
+
+    // the lowest ranked bot that hasn't had one sits the round out
+    let bye = (unpaired.len() % 2 == 1).then(|| {
+        let bye = unpaired
+            .iter()
+            .rposition(|bot| standings[*bot].byes == 0)
+            .unwrap_or(unpaired.len() - 1);
+        unpaired.remove(bye)
+    });
+
+    // everyone plays the best ranked bot left that they haven't met,
+    // or the best ranked one left when they have met them all
+    let mut pairings = Vec::new();
+    while !unpaired.is_empty() {
+        let a = unpaired.remove(0);
+        let b = unpaired
+            .iter()
+            .position(|b| !standings[a].opponents.contains(b))
+            .unwrap_or(0);
+        pairings.push((a, unpaired.remove(b)));
+    }
+    (bye, pairings)
+}
+
+struct Tournament {
+    simulation: Simulation,
+    standings: Vec<Standing>,
+    seeds: ChaCha8Rng,
+    matches: u32,
+}
+
+impl Tournament {
+    /// Plays the matches between bots `a` and `b`, swapping sides after each one.
+    fn play_pairing(&mut self, a: usize, b: usize) {
+        for game in 0..self.matches {
+            let (right, left) = if game % 2 == 0 { (a, b) } else { (b, a) };
+            let seed = self.seeds.gen();
+            let result = self.simulation.play(
+                &self.standings[right].name,
+                &self.standings[left].name,
+                seed,
+            );
+
+            println!(
+                "{} {} - {} {}{} (seed {}, {} frames)",
+                self.standings[left].name,
+                result.score.1,
+                result.score.0,
+                self.standings[right].name,
+                if result.outcome == Outcome::Draw {
+                    ", drawn"
+                } else {
+                    ""
+                },
+                seed,
+                result.frames
+            );
+
+            let expected = expected_score(self.standings[right].elo, self.standings[left].elo);
+            let change = ELO_K * (result.outcome.points() - expected);
+            self.standings[right].elo += change;
+            self.standings[left].elo -= change;
+            self.standings[right].record(result.outcome, &result.rallies);
+            self.standings[left].record(result.outcome.reversed(), &result.rallies);
+        }
+        self.standings[a].opponents.push(b);
+        self.standings[b].opponents.push(a);
+    }
+
+    fn round_robin(&mut self) {
+        let count = self.standings.len();
+        for a in 0..count {
+            for b in a + 1..count {
+                self.play_pairing(a, b);
+            }
+        }
+    }
+
+    fn swiss(&mut self, rounds: u32) {
+        for round in 1..=rounds {
+            println!("round {}", round);
+            let (bye, pairings) = swiss_pairings(&self.standings);
+            if let Some(bot) = bye {
+                self.standings[bot].byes += 1;
+                println!("{} has a bye", self.standings[bot].name);
+            }
+            for (a, b) in pairings {
+                self.play_pairing(a, b);
+            }
+        }
+    }
+
+    fn report(&self) {
+        let mut ranked: Vec<&Standing> = self.standings.iter().collect();
+        ranked.sort_by(|a, b| b.elo.total_cmp(&a.elo));
+        let width = ranked
+            .iter()
+            .map(|standing| standing.name.len())
+            .max()
+            .unwrap_or_default()
+            .max(3);
+
+        println!();
+        println!(
+            "{:<width$}  played  won  lost  drawn  win rate   elo  avg rally",
+            "bot"
+        );
+        for standing in ranked {
+            println!(
+                "{:<width$}  {:>6}  {:>3}  {:>4}  {:>5}  {:>7.1}%  {:>4.0}  {:>9.1}",
+                standing.name,
+                standing.played(),
+                standing.wins,
+                standing.losses,
+                standing.draws,
+                100. * standing.win_rate(),
+                standing.elo,
+                standing.average_rally()
+            );
+        }
+    }
+}
+
+fn run(args: Args) -> anyhow::Result<()> {
+    for (index, name) in args.bots.iter().enumerate() {
+        if args.bots[..index].contains(name) {
+            bail!("`{}` is in the tournament twice", name);
+        }
+    }
+    if args.bots.len() < 2 {
+        bail!("a tournament needs at least two bots");
+    }
+    let level = match &args.level {
+        Some(path) => {
+            Level::load(path).with_context(|| format!("could not load level {}", path.display()))?
+        }
+        None => Level::default(),
+    };
+
+    let simulation = Simulation::new(&args, level)?;
+    let seed = args.seed.unwrap_or_else(rand::random);
+    println!("tournament seed {}", seed);
+    let mut tournament = Tournament {
+        simulation,
+        standings: args.bots.iter().map(|name| Standing::new(name)).collect(),
+        seeds: ChaCha8Rng::seed_from_u64(seed),
+        matches: args.matches,
+    };
+
+    match args.format {
+        Format::RoundRobin => tournament.round_robin(),
+        Format::Swiss => {
+            // enough rounds for one bot to come out unbeaten
+            let rounds = args
+                .rounds
+                .unwrap_or_else(|| args.bots.len().next_power_of_two().trailing_zeros());
+            tournament.swiss(rounds.max(1));
+        }
+    }
+    tournament.report();
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run(Args::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {:#}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Bots named after their index, ranked in that order by their rating.
+    fn standings(count: usize) -> Vec<Standing> {
+        (0..count)
+            .map(|index| {
+                let mut standing = Standing::new(&index.to_string());
+                standing.elo -= index as f32;
+                standing
+            })
+            .collect()
+    }
+
+    #[test]
+    fn expected_score_favors_the_higher_rating() {
+        assert_eq!(expected_score(1500., 1500.), 0.5);
+        assert!((expected_score(1900., 1500.) - 10. / 11.).abs() < 1e-6);
+        let (a, b) = (expected_score(1620., 1480.), expected_score(1480., 1620.));
+        assert!(a > 0.5);
+        assert!((a + b - 1.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn points_count_draws_as_half_and_byes_as_wins() {
+        let mut standing = Standing::new("bot");
+        standing.record(Outcome::Win, &[]);
+        standing.record(Outcome::Win, &[]);
+        standing.record(Outcome::Draw, &[]);
+        standing.record(Outcome::Loss, &[]);
+        standing.byes = 1;
+        assert_eq!(standing.points(), 3.5);
+        assert_eq!(standing.played(), 4);
+    }
+
+    #[test]
+    fn swiss_pairs_neighbours_in_the_ranking() {
+        let mut standings = standings(4);
+        standings[3].wins = 1;
+        assert_eq!(swiss_pairings(&standings), (None, vec![(3, 0), (1, 2)]));
+    }
+
+    #[test]
+    fn swiss_avoids_rematches() {
+        let mut standings = standings(4);
+        standings[0].opponents.push(1);
+        standings[1].opponents.push(0);
+        assert_eq!(swiss_pairings(&standings), (None, vec![(0, 2), (1, 3)]));
+    }
+
+    #[test]
+    fn swiss_allows_a_rematch_when_everyone_was_met() {
+        let mut standings = standings(2);
+        standings[0].opponents.push(1);
+        standings[1].opponents.push(0);
+        assert_eq!(swiss_pairings(&standings), (None, vec![(0, 1)]));
+    }
+
+    #[test]
+    fn swiss_bye_goes_to_the_lowest_ranked_bot_without_one() {
+        let mut standings = standings(3);
+        assert_eq!(swiss_pairings(&standings), (Some(2), vec![(0, 1)]));
+
+        standings[2].byes = 1;
+        assert_eq!(swiss_pairings(&standings), (Some(1), vec![(2, 0)]));
+
+        // everyone had one, the lowest ranked bot sits out again
+        standings[0].byes = 1;
+        standings[1].byes = 1;
+        assert_eq!(swiss_pairings(&standings), (Some(2), vec![(0, 1)]));
+    }
+
+    #[test]
+    fn average_rally_covers_every_match() {
+        let mut standing = Standing::new("bot");
+        assert_eq!(standing.average_rally(), 0.);
+        standing.record(Outcome::Win, &[2, 4]);
+        standing.record(Outcome::Loss, &[6]);
+        assert_eq!(standing.average_rally(), 4.);
+    }
+}
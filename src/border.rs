@@ -1,10 +1,16 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
-use crate::utils::{Position, Shape};
+use crate::{
+    game_manager::{GameState, GameplaySet, MatchSetup, Score, Scored},
+    team::{TeamGoal, Teams},
+    utils::{Position, Shape, ARENA_SIZE},
+};
 
 const INFINITE: f32 = 100000.;
+const THICKNESS: f32 = 20.;
 
-#[derive(Component)]
+#[derive(Component, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Border {
     Left,
     Right,
@@ -13,6 +19,11 @@ pub enum Border {
 }
 
 impl Border {
+    /// Unit vector from the center of the court towards this border.
+    pub fn outward(&self) -> Vec2 {
+        self.get_position(1., 1.).0
+    }
+
     fn get_position(&self, width: f32, height: f32) -> Position {
         match self {
             Border::Left => Position(Vec2::new(-width, 0.)),
@@ -30,57 +41,107 @@ struct BorderBundle {
     shape: Shape,
 }
 
-fn spawn(mut commands: Commands, window: Query<&Window>) {
-    let win = window.get_single().unwrap();
-    let height = win.resolution.height() / 2.;
-    let width = win.resolution.width() / 2.;
-    println!("screen dimensions, height: {}, width: {}", height, width);
+fn spawn(mut commands: Commands) {
+    let height = ARENA_SIZE.y / 2.;
+    let width = ARENA_SIZE.x / 2.;
 
     let vertical = Shape::Rectangle {
-        width: 20.,
+        width: THICKNESS,
         height: INFINITE,
     };
 
     let horizontal = Shape::Rectangle {
         width: INFINITE,
-        height: 20.,
+        height: THICKNESS,
     };
 
-    commands.spawn(BorderBundle {
-        border: Border::Left,
-        position: Border::Left.get_position(width, height),
-        shape: vertical.clone(),
-    });
-
-    commands.spawn(BorderBundle {
-        border: Border::Right,
-        position: Border::Right.get_position(width, height),
-        shape: vertical.clone(),
-    });
-
-    commands.spawn(BorderBundle {
-        border: Border::Top,
-        position: Border::Top.get_position(width, height),
-        shape: horizontal.clone(),
-    });
-
-    commands.spawn(BorderBundle {
-        border: Border::Bottom,
-        position: Border::Bottom.get_position(width, height),
-        shape: horizontal.clone(),
-    });
+    for (border, shape) in [
+        (Border::Left, &vertical),
+        (Border::Right, &vertical),
+        (Border::Top, &horizontal),
+        (Border::Bottom, &horizontal),
+    ] {
+        commands.spawn(BorderBundle {
+            border,
+            position: border.get_position(width, height),
+            shape: shape.clone(),
+        });
+    }
+}
+
+/// Marks the goals of the teams in the new match. A border that is nobody's
+/// goal is just a wall.
+fn assign_goals(mut commands: Commands, borders: Query<(Entity, &Border)>, teams: Res<Teams>) {
+    for (entity, border) in &borders {
+        match teams.owner(*border) {
+            Some(team) => commands.entity(entity).insert(TeamGoal(team)),
+            None => commands.entity(entity).remove::<TeamGoal>(),
+        };
+    }
+}
+
+/// Closes the goal of a team knocked out of the match.
+#[derive(Component)]
+struct GoalWall(Border);
+
+impl GoalWall {
+    /// Just in front of the border, so the ball bounces off the wall before
+    /// it can reach the goal behind it.
+    fn position(&self, width: f32, height: f32) -> Vec2 {
+        self.0.get_position(width, height).0 - self.0.outward() * THICKNESS
+    }
 }
 
-fn adjust_border_position(
-    mut borders: Query<(&mut Position, &Border), With<Border>>,
-    window: Query<&Window>,
+fn close_goals(
+    mut commands: Commands,
+    mut events: EventReader<Scored>,
+    score: Res<Score>,
+    teams: Res<Teams>,
+    setup: Res<MatchSetup>,
 ) {
-    let win = window.get_single().unwrap();
-    let height = win.height() / 2.;
-    let width = win.width() / 2.;
+    let size = ARENA_SIZE;
+    let knocked_out = events
+        .read()
+        .filter(|event| score.is_out(event.against, &teams, setup.target_score));
+    for side in knocked_out.flat_map(|event| &teams.get(event.against).goals) {
+        let wall = GoalWall(*side);
+        let position = wall.position(size.x / 2., size.y / 2.);
+        let (shape, sprite_size) = match side {
+            Border::Left | Border::Right => (
+                Shape::Rectangle {
+                    width: THICKNESS,
+                    height: INFINITE,
+                },
+                Vec2::new(THICKNESS, size.y),
+            ),
+            Border::Top | Border::Bottom => (
+                Shape::Rectangle {
+                    width: INFINITE,
+                    height: THICKNESS,
+                },
+                Vec2::new(size.x, THICKNESS),
+            ),
+        };
+        commands.spawn((
+            wall,
+            Position(position),
+            shape,
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::GRAY,
+                    custom_size: Some(sprite_size),
+                    ..default()
+                },
+                transform: Transform::from_translation(position.extend(0.)),
+                ..default()
+            },
+        ));
+    }
+}
 
-    for (mut position, border) in &mut borders {
-        position.0 = border.get_position(width, height).0;
+fn remove_goal_walls(mut commands: Commands, walls: Query<Entity, With<GoalWall>>) {
+    for entity in &walls {
+        commands.entity(entity).despawn_recursive();
     }
 }
 
@@ -88,6 +149,10 @@ pub struct BordersPlugin;
 impl Plugin for BordersPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, spawn);
-        app.add_systems(Update, adjust_border_position);
+        app.add_systems(
+            OnEnter(GameState::Playing),
+            (assign_goals, remove_goal_walls),
+        );
+        app.add_systems(Update, close_goals.in_set(GameplaySet));
     }
 }
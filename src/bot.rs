@@ -0,0 +1,177 @@
+use std::collections::BTreeMap;
+
+use anyhow::bail;
+use bevy::prelude::*;
+use serde::Serialize;
+
+use crate::{border::Border, game_manager::MatchSetup};
+
+/// The bot driving AI paddles that were not given one.
+pub const DEFAULT_BOT: &str = "follow";
+
+/// A paddle as a bot sees it.
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+pub struct PaddleState {
+    pub position: Vec2,
+    pub velocity: Vec2,
+    /// Unit direction the paddle slides along, a positive `MoveCommand` moves
+    /// it this way.
+    pub rail: Vec2,
+    /// The border the paddle guards.
+    pub goal: Border,
+}
+
+/// What a bot is told about the match every frame.
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct Observation {
+    /// The paddle the bot moves.
+    pub paddle: PaddleState,
+    /// The paddles of the other teams still on the court.
+    pub opponents: Vec<PaddleState>,
+    pub ball_position: Vec2,
+    /// In units per frame.
+    pub ball_velocity: Vec2,
+    /// Size of the court, centered on the origin.
+    pub arena: Vec2,
+    /// What counts on the scoreboard for the bot's team, see `Score::counted`,
+    /// and the most any other team has.
+    pub score: u32,
+    pub opponent_score: u32,
+}
+
+/// How fast to move along the rail, from -1 to 1 of the paddle's full speed.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct MoveCommand(pub f32);
+
+/// Moves a paddle. Every AI paddle has its own controller, which can keep
+/// whatever state it needs from one frame to the next.
+pub trait PaddleController: Send + Sync + 'static {
+    fn act(&mut self, observation: &Observation) -> MoveCommand;
+}
+
+type BotFactory = Box<dyn Fn() -> Box<dyn PaddleController> + Send + Sync>;
+
+/// Every bot that can be put on a paddle, by name.
+#[derive(Resource, Default)]
+pub struct BotRegistry(BTreeMap<String, BotFactory>);
+
+impl BotRegistry {
+    /// A new controller of the bot called `name`.
+    pub fn create(&self, name: &str) -> Option<Box<dyn PaddleController>> {
+        self.0.get(name).map(|factory| factory())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(String::as_str)
+    }
+
+    /// Fails on a bot in `setup` that was never registered.
+    pub fn check(&self, setup: &MatchSetup) -> anyhow::Result<()> {
+        self.check_names(&setup.bots)
+    }
+
+    /// Fails on any of `names` that was never registered.
+    pub fn check_names(&self, names: &[String]) -> anyhow::Result<()> {
+        for name in names {
+            if !self.0.contains_key(name) {
+                let known: Vec<&str> = self.names().collect();
+                bail!("unknown bot `{}`, pick one of {}", name, known.join(", "));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub trait RegisterBot {
+    /// Makes the bot built by `factory` available as `name`, replacing any
+    /// bot of the same name.
+    fn register_bot<C: PaddleController>(
+        &mut self,
+        name: &str,
+        factory: impl Fn() -> C + Send + Sync + 'static,
+    ) -> &mut Self;
+}
+
+impl RegisterBot for App {
+    fn register_bot<C: PaddleController>(
+        &mut self,
+        name: &str,
+        factory: impl Fn() -> C + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.world
+            .get_resource_or_insert_with(BotRegistry::default)
+            .0
+            .insert(name.to_string(), Box::new(move || Box::new(factory())));
+        self
+    }
+}
+
+/// Heads straight for the ball, wherever it is going.
+#[derive(Default)]
+pub struct Follow;
+impl PaddleController for Follow {
+    fn act(&mut self, observation: &Observation) -> MoveCommand {
+        let paddle = &observation.paddle;
+        let diff = (paddle.position - observation.ball_position).dot(paddle.rail);
+        if diff > 0. {
+            MoveCommand(-1.)
+        } else if diff < 0. {
+            MoveCommand(1.)
+        } else {
+            MoveCommand(0.)
+        }
+    }
+}
+
+/// Works out where the ball will cross its line, bouncing off the side walls,
+/// and waits there. Goes back to the middle while the ball moves away.
+#[derive(Default)]
+pub struct Predict;
+impl PaddleController for Predict {
+    fn act(&mut self, observation: &Observation) -> MoveCommand {
+        let paddle = &observation.paddle;
+        let outward = paddle.goal.outward();
+        let approach = observation.ball_velocity.dot(outward);
+        let target = if approach > 0. {
+            let distance = (paddle.position - observation.ball_position).dot(outward);
+            let frames = distance.max(0.) / approach;
+            let along = observation.ball_position + observation.ball_velocity * frames;
+            fold(
+                along.dot(paddle.rail),
+                observation.arena.dot(paddle.rail) / 2.,
+            )
+        } else {
+            0.
+        };
+
+        let diff = target - paddle.position.dot(paddle.rail);
+        // close enough, don't jitter around the spot
+        if diff.abs() < 5. {
+            MoveCommand(0.)
+        } else {
+            MoveCommand(diff.signum())
+        }
+    }
+}
+
+/// Where a point moving freely to `x` ends up after bouncing between walls
+/// at `-half` and `half`.
+fn fold(x: f32, half: f32) -> f32 {
+    let period = 4. * half;
+    let x = (x + half).rem_euclid(period);
+    if x < 2. * half {
+        x - half
+    } else {
+        3. * half - x
+    }
+}
+
+/// Keeps the controllers AI paddles can be given. `follow` and `predict` are built in.
+pub struct BotPlugin;
+impl Plugin for BotPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BotRegistry>()
+            .register_bot(DEFAULT_BOT, || Follow)
+            .register_bot("predict", || Predict);
+    }
+}
@@ -0,0 +1,205 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use bevy::window::{Window, WindowMode, WindowResolution};
+use clap::{Parser, ValueEnum};
+
+use crate::{
+    clock::{TieBreak, TimedMatch},
+    game_manager::{GameMode, MatchSetup},
+    level::Level,
+    paddle::Controller,
+    replay::Replay,
+    settings::{Difficulty, Settings},
+};
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Mode {
+    /// Play against the computer.
+    VsAi,
+    /// Two players on one keyboard.
+    #[value(name = "local-2p")]
+    LocalTwoPlayer,
+    /// Watch the computer play itself.
+    AiVsAi,
+    /// Four players, one on each side of the court.
+    Quadrapong,
+    /// Play against the far wall, with no score to reach.
+    Practice,
+    /// Simulate one AI vs AI match without a window and print the result.
+    Headless,
+}
+
+/// Launch options. Anything left out falls back to the saved settings.
+#[derive(Parser, Debug)]
+#[command(version, about = "Pong, built with Bevy")]
+pub struct Cli {
+    /// Start a match in this mode right away instead of showing the main menu.
+    #[arg(long, value_enum)]
+    pub mode: Option<Mode>,
+
+    /// AI difficulty.
+    #[arg(long, value_enum)]
+    pub difficulty: Option<Difficulty>,
+
+    /// Controllers of the right, left, top and bottom paddles in Quadrapong,
+    /// e.g. `p1,ai,p2,ai`.
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub seats: Option<Vec<Controller>>,
+
+    /// Bots driving the AI paddles on the right, left, top and bottom, e.g.
+    /// `follow,predict`. Sides left out keep the default bot.
+    #[arg(long, value_delimiter = ',', num_args = 1..=4)]
+    pub bots: Option<Vec<String>>,
+
+    /// Adds a bot run by another program, to pick with `--bots`. COMMAND runs
+    /// the bot and talks to it over stdin and stdout, or is `tcp:<address>` of
+    /// a bot listening there, e.g. `echo=target/debug/examples/echo_bot`.
+    #[arg(long, value_name = "NAME=COMMAND", value_parser = external_bot)]
+    pub external_bot: Vec<(String, String)>,
+
+    /// How long external bots have to answer every frame.
+    #[arg(long, value_name = "MS", default_value_t = 8)]
+    pub bot_timeout_ms: u64,
+
+    /// Points needed to win the match, or goals a side can let in before it is
+    /// out in Quadrapong.
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub target_score: Option<u32>,
+
+    /// Play a timed match of this many seconds, won by whoever leads at the end.
+    #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u32).range(1..))]
+    pub time_limit: Option<u32>,
+
+    /// What settles a timed match that ends in a tie.
+    #[arg(long, value_enum, requires = "time_limit")]
+    pub tie_break: Option<TieBreak>,
+
+    /// Seed for serves and other random events, to reproduce a match.
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Window width in logical pixels.
+    #[arg(long, value_parser = window_size_arg, requires = "height")]
+    pub width: Option<f32>,
+
+    /// Window height in logical pixels.
+    #[arg(long, value_parser = window_size_arg, requires = "width")]
+    pub height: Option<f32>,
+
+    #[arg(long)]
+    pub fullscreen: bool,
+
+    /// Replay file to play back instead of starting a match.
+    #[arg(long, value_name = "FILE")]
+    pub replay: Option<PathBuf>,
+
+    /// Level file with extra obstacles.
+    #[arg(long, value_name = "FILE")]
+    pub level: Option<PathBuf>,
+}
+
+fn window_size_arg(value: &str) -> Result<f32, String> {
+    let size: f32 = value
+        .parse()
+        .map_err(|_| format!("`{}` is not a number", value))?;
+    if size.is_finite() && size >= 100. {
+        Ok(size)
+    } else {
+        Err("must be at least 100".to_string())
+    }
+}
+
+/// Parses `--external-bot NAME=COMMAND`.
+pub fn external_bot(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((name, command)) if !name.is_empty() && !command.trim().is_empty() => {
+            Ok((name.to_string(), command.to_string()))
+        }
+        _ => Err("expected NAME=COMMAND".to_string()),
+    }
+}
+
+impl Cli {
+    /// Checks the options that clap can't, and loads the files they point to.
+    pub fn validate(&self) -> anyhow::Result<(Level, Option<Replay>)> {
+        if self.replay.is_some() && self.mode == Some(Mode::Headless) {
+            anyhow::bail!("--replay needs a window, it can't be combined with --mode headless");
+        }
+        if self.replay.is_some() && matches!(self.mode, Some(Mode::Quadrapong | Mode::Practice)) {
+            anyhow::bail!("only two-sided matches can be replayed");
+        }
+        if let Some(seats) = &self.seats {
+            if self.mode != Some(Mode::Quadrapong) {
+                anyhow::bail!("--seats only applies to --mode quadrapong");
+            }
+            if seats.len() != 4 {
+                anyhow::bail!("--seats needs four controllers, got {}", seats.len());
+            }
+        }
+
+        let level = match &self.level {
+            Some(path) => Level::load(path)
+                .with_context(|| format!("could not load level {}", path.display()))?,
+            None => Level::default(),
+        };
+        let replay = match &self.replay {
+            Some(path) => Some(
+                Replay::load(path)
+                    .with_context(|| format!("could not load replay {}", path.display()))?,
+            ),
+            None => None,
+        };
+        Ok((level, replay))
+    }
+
+    pub fn match_setup(&self, settings: &Settings) -> MatchSetup {
+        let mut setup = MatchSetup::from_settings(settings);
+        if let Some(seats) = &self.seats {
+            if let Ok(seats) = seats.as_slice().try_into() {
+                setup.seats = seats;
+            }
+        }
+        for (bot, name) in setup.bots.iter_mut().zip(self.bots.iter().flatten()) {
+            bot.clone_from(name);
+        }
+        if let Some(difficulty) = self.difficulty {
+            setup.difficulty = difficulty;
+        }
+        if let Some(target_score) = self.target_score {
+            setup.target_score = target_score;
+        }
+        if let Some(seed) = self.seed {
+            setup.seed = seed;
+        }
+        if let Some(secs) = self.time_limit {
+            let timed = setup.timed.get_or_insert_with(TimedMatch::default);
+            timed.secs = secs;
+            if let Some(tie_break) = self.tie_break {
+                timed.tie_break = tie_break;
+            }
+        }
+        setup.with_mode(match self.mode.unwrap_or(Mode::VsAi) {
+            Mode::VsAi => GameMode::VsAi,
+            Mode::LocalTwoPlayer => GameMode::LocalTwoPlayer,
+            Mode::AiVsAi | Mode::Headless => GameMode::AiVsAi,
+            Mode::Quadrapong => GameMode::Quadrapong,
+            Mode::Practice => GameMode::Practice,
+        })
+    }
+
+    /// Whether to open on the main menu rather than go straight into a match.
+    pub fn opens_menu(&self) -> bool {
+        self.mode.is_none() && self.replay.is_none()
+    }
+
+    pub fn window(&self, mut window: Window) -> Window {
+        if let (Some(width), Some(height)) = (self.width, self.height) {
+            window.resolution = WindowResolution::new(width, height);
+        }
+        if self.fullscreen {
+            window.mode = WindowMode::BorderlessFullscreen;
+        }
+        window
+    }
+}
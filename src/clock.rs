@@ -0,0 +1,169 @@
+use bevy::prelude::*;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game_manager::{
+        countdown_guard, detect_match_end, score_winner, AllowedToRun, GameState, GameplaySet,
+        MatchFinished, MatchSetup, Score, Scored,
+    },
+    team::Teams,
+    time_scale::TimeScale,
+};
+
+/// The clock starts warning the players this many seconds before it runs out.
+pub const WARNING_SECS: f32 = 10.;
+
+/// What happens when the clock runs out on a tie.
+#[derive(Serialize, Deserialize, ValueEnum, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum TieBreak {
+    /// Another period of `TimedMatch::overtime_secs`, then sudden death if
+    /// it's still a tie.
+    #[default]
+    Overtime,
+    /// The next goal wins.
+    SuddenDeath,
+}
+
+/// A match won by whoever leads when the clock runs out, instead of the first
+/// to the target score.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(default)]
+pub struct TimedMatch {
+    pub secs: u32,
+    pub tie_break: TieBreak,
+    pub overtime_secs: u32,
+}
+
+impl Default for TimedMatch {
+    fn default() -> Self {
+        TimedMatch {
+            secs: 180,
+            tie_break: TieBreak::default(),
+            overtime_secs: 60,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ClockPhase {
+    Regulation,
+    Overtime,
+    /// The clock is stopped and the next goal that breaks the tie wins.
+    SuddenDeath,
+}
+
+/// Time left in a timed match. Only present when the match is timed.
+#[derive(Resource, Debug)]
+pub struct MatchClock {
+    pub remaining: f32,
+    pub phase: ClockPhase,
+}
+
+impl MatchClock {
+    /// Whether the clock should draw attention to itself.
+    pub fn is_running_out(&self) -> bool {
+        self.phase != ClockPhase::SuddenDeath && self.remaining <= WARNING_SECS
+    }
+}
+
+fn start_clock(mut commands: Commands, setup: Res<MatchSetup>) {
+    match setup.timed {
+        Some(timed) => commands.insert_resource(MatchClock {
+            remaining: timed.secs as f32,
+            phase: ClockPhase::Regulation,
+        }),
+        None => commands.remove_resource::<MatchClock>(),
+    }
+}
+
+fn run_clock(
+    In(allowed): In<AllowedToRun>,
+    mut clock: ResMut<MatchClock>,
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    setup: Res<MatchSetup>,
+    score: Res<Score>,
+    teams: Res<Teams>,
+    mut events: EventWriter<MatchFinished>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !allowed || clock.phase == ClockPhase::SuddenDeath {
+        return;
+    }
+    // a knock-out decided the match already, `detect_match_end` ends it
+    if score_winner(&score, &teams, &setup).is_some() {
+        return;
+    }
+    clock.remaining = (clock.remaining - time_scale.scaled(time.delta()).as_secs_f32()).max(0.);
+    if clock.remaining > 0. {
+        return;
+    }
+
+    if let Some(winner) = score.leader(&teams, setup.target_score) {
+        events.send(MatchFinished {
+            final_score: score.clone(),
+            winner,
+        });
+        next_state.set(GameState::MatchOver);
+        return;
+    }
+
+    let Some(timed) = setup.timed else {
+        return;
+    };
+    *clock = match (clock.phase, timed.tie_break) {
+        (ClockPhase::Regulation, TieBreak::Overtime) => MatchClock {
+            remaining: timed.overtime_secs as f32,
+            phase: ClockPhase::Overtime,
+        },
+        _ => MatchClock {
+            remaining: 0.,
+            phase: ClockPhase::SuddenDeath,
+        },
+    };
+}
+
+fn sudden_death(
+    clock: Res<MatchClock>,
+    mut scored: EventReader<Scored>,
+    setup: Res<MatchSetup>,
+    score: Res<Score>,
+    teams: Res<Teams>,
+    mut events: EventWriter<MatchFinished>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if scored.read().count() == 0 || clock.phase != ClockPhase::SuddenDeath {
+        return;
+    }
+    if score_winner(&score, &teams, &setup).is_some() {
+        return;
+    }
+
+    if let Some(winner) = score.leader(&teams, setup.target_score) {
+        events.send(MatchFinished {
+            final_score: score.clone(),
+            winner,
+        });
+        next_state.set(GameState::MatchOver);
+    }
+}
+
+/// Runs the clock of timed matches. Like the rest of the match it stands
+/// still during countdowns and replays.
+pub struct ClockPlugin;
+impl Plugin for ClockPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Playing), start_clock)
+            .add_systems(
+                Update,
+                (
+                    countdown_guard.pipe(run_clock),
+                    sudden_death.run_if(in_state(GameState::Playing)),
+                )
+                    .run_if(resource_exists::<MatchClock>)
+                    .after(detect_match_end)
+                    .in_set(GameplaySet),
+            );
+    }
+}
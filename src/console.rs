@@ -0,0 +1,236 @@
+use std::collections::BTreeMap;
+
+use anyhow::bail;
+use bevy::{input::InputSystem, prelude::*, window::ReceivedCharacter};
+
+/// Lines of output kept on screen.
+const LOG_LINES: usize = 12;
+const FONT_SIZE: f32 = 18.;
+
+/// A command of the developer console, such as `spawn ball`. Plugins add
+/// their own with `App::register_command`.
+pub trait ConsoleCommand: Send + Sync + 'static {
+    /// The first word typed, which picks the command.
+    fn name(&self) -> &'static str;
+    /// The command with its arguments, as listed by `help`.
+    fn usage(&self) -> &'static str;
+    /// Runs the command with the words typed after its name, returning the
+    /// line to print.
+    fn run(&self, args: &[&str], world: &mut World) -> anyhow::Result<String>;
+}
+
+/// Every console command, by name.
+#[derive(Resource, Default)]
+pub struct ConsoleCommands(BTreeMap<&'static str, Box<dyn ConsoleCommand>>);
+
+impl ConsoleCommands {
+    /// Runs a line typed into the console.
+    pub fn execute(&self, line: &str, world: &mut World) -> anyhow::Result<String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some((name, args)) = words.split_first() else {
+            return Ok(String::new());
+        };
+        if *name == "help" {
+            let usages: Vec<&str> = self.0.values().map(|command| command.usage()).collect();
+            return Ok(usages.join("\n"));
+        }
+        match self.0.get(name) {
+            Some(command) => command.run(args, world),
+            None => bail!("unknown command `{}`, try `help`", name),
+        }
+    }
+}
+
+pub trait RegisterCommand {
+    /// Adds `command` to the console, replacing any command of the same name.
+    fn register_command(&mut self, command: impl ConsoleCommand) -> &mut Self;
+}
+
+impl RegisterCommand for App {
+    fn register_command(&mut self, command: impl ConsoleCommand) -> &mut Self {
+        self.world
+            .get_resource_or_insert_with(ConsoleCommands::default)
+            .0
+            .insert(command.name(), Box::new(command));
+        self
+    }
+}
+
+/// Whether the console is dropped down.
+#[derive(States, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConsoleState {
+    #[default]
+    Closed,
+    Open,
+}
+
+/// What is typed into the console and what it printed.
+#[derive(Resource, Default)]
+struct Console {
+    input: String,
+    log: Vec<String>,
+    /// Lines entered earlier, oldest first, and the one brought back with the arrows.
+    history: Vec<String>,
+    browsing: Option<usize>,
+    /// Lines entered this frame, waiting to run.
+    pending: Vec<String>,
+}
+
+#[derive(Component)]
+struct ConsolePanel;
+
+#[derive(Component)]
+struct ConsoleText;
+
+fn toggle_console(
+    keys: Res<ButtonInput<KeyCode>>,
+    state: Res<State<ConsoleState>>,
+    mut next_state: ResMut<NextState<ConsoleState>>,
+) {
+    if keys.just_pressed(KeyCode::Backquote) {
+        next_state.set(match state.get() {
+            ConsoleState::Closed => ConsoleState::Open,
+            ConsoleState::Open => ConsoleState::Closed,
+        });
+    }
+}
+
+/// Edits the input line. The keyboard belongs to the console while it is
+/// open, so the keys are cleared before the game gets to see them.
+fn type_input(
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut characters: EventReader<ReceivedCharacter>,
+    mut console: ResMut<Console>,
+    mut next_state: ResMut<NextState<ConsoleState>>,
+) {
+    for event in characters.read() {
+        let typed = event.char.chars().filter(|c| !c.is_control() && *c != '`');
+        console.input.extend(typed);
+    }
+
+    if keys.just_pressed(KeyCode::Backspace) {
+        console.input.pop();
+    }
+    if keys.just_pressed(KeyCode::Enter) {
+        let line = std::mem::take(&mut console.input);
+        if !line.trim().is_empty() {
+            console.history.push(line.clone());
+            console.pending.push(line);
+        }
+        console.browsing = None;
+    }
+    if keys.just_pressed(KeyCode::ArrowUp) && !console.history.is_empty() {
+        let index = match console.browsing {
+            Some(index) => index.saturating_sub(1),
+            None => console.history.len() - 1,
+        };
+        console.browsing = Some(index);
+        console.input = console.history[index].clone();
+    }
+    if keys.just_pressed(KeyCode::ArrowDown) {
+        if let Some(index) = console.browsing {
+            if index + 1 < console.history.len() {
+                console.browsing = Some(index + 1);
+                console.input = console.history[index + 1].clone();
+            } else {
+                console.browsing = None;
+                console.input.clear();
+            }
+        }
+    }
+    if keys.just_pressed(KeyCode::Escape) {
+        next_state.set(ConsoleState::Closed);
+    }
+
+    keys.reset_all();
+}
+
+fn run_commands(world: &mut World) {
+    let lines = std::mem::take(&mut world.resource_mut::<Console>().pending);
+    for line in lines {
+        let output = world
+            .resource_scope(|world, commands: Mut<ConsoleCommands>| commands.execute(&line, world));
+        let mut console = world.resource_mut::<Console>();
+        console.log.push(format!("> {}", line));
+        match output {
+            Ok(output) => console.log.extend(output.lines().map(str::to_string)),
+            Err(err) => console.log.push(format!("error: {:#}", err)),
+        }
+    }
+}
+
+fn spawn_console(mut commands: Commands) {
+    commands
+        .spawn((
+            ConsolePanel,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.),
+                    padding: UiRect::all(Val::Px(8.)),
+                    ..default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.85).into(),
+                z_index: ZIndex::Global(20),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                ConsoleText,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: FONT_SIZE,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+            ));
+        });
+}
+
+fn despawn_console(mut commands: Commands, panel: Query<Entity, With<ConsolePanel>>) {
+    for entity in &panel {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn update_text(console: Res<Console>, mut text: Query<&mut Text, With<ConsoleText>>) {
+    let Ok(mut text) = text.get_single_mut() else {
+        return;
+    };
+    let shown = console.log.len().saturating_sub(LOG_LINES);
+    let mut lines: Vec<&str> = console.log[shown..].iter().map(String::as_str).collect();
+    let prompt = format!("> {}_", console.input);
+    lines.push(&prompt);
+    text.sections[0].value = lines.join("\n");
+}
+
+/// A drop-down console, opened with the backtick key, to change the game
+/// while it runs. Type `help` for the commands.
+pub struct ConsolePlugin;
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ConsoleCommands>()
+            .init_resource::<Console>()
+            .init_state::<ConsoleState>()
+            .add_systems(OnEnter(ConsoleState::Open), spawn_console)
+            .add_systems(OnExit(ConsoleState::Open), despawn_console)
+            .add_systems(
+                PreUpdate,
+                (
+                    toggle_console,
+                    type_input.run_if(in_state(ConsoleState::Open)),
+                )
+                    .chain()
+                    .after(InputSystem),
+            )
+            .add_systems(
+                Update,
+                (run_commands, update_text)
+                    .chain()
+                    .run_if(in_state(ConsoleState::Open)),
+            );
+    }
+}
@@ -0,0 +1,188 @@
+use bevy::{ecs::system::EntityCommands, prelude::*, sprite::ImageScaleMode};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    border::Border,
+    team::TeamGoal,
+    utils::{asset_path, load_ron_or_default, Position, Shape, ARENA_SIZE},
+};
+
+const THEME_PATH: &str = "config/theme.ron";
+
+/// How the court looks. Loaded from `assets/config/theme.ron`; textures are
+/// paths under `assets/` and are tiled, tinted with the matching color.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Theme {
+    pub background: Color,
+    pub background_texture: Option<String>,
+    pub wall: Color,
+    pub wall_texture: Option<String>,
+    /// Color of the borders that are a team's goal.
+    pub goal: Color,
+    pub net: Color,
+    pub net_width: f32,
+    /// Length of each dash of the net and of the space between two dashes.
+    pub net_dash: f32,
+    pub net_gap: f32,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            background: Color::rgb(0.17, 0.17, 0.18),
+            background_texture: None,
+            wall: Color::rgb(0.8, 0.8, 0.8),
+            wall_texture: None,
+            goal: Color::rgb(0.9, 0.75, 0.2),
+            net: Color::rgba(1., 1., 1., 0.4),
+            net_width: 6.,
+            net_dash: 24.,
+            net_gap: 16.,
+        }
+    }
+}
+
+impl Theme {
+    /// Gives `entity` a plain colored sprite, tiled with `texture` when the
+    /// theme has one.
+    fn insert_sprite(
+        &self,
+        entity: &mut EntityCommands,
+        assets: &AssetServer,
+        color: Color,
+        texture: &Option<String>,
+        size: Vec2,
+        transform: Transform,
+    ) {
+        let mut bundle = SpriteBundle {
+            sprite: Sprite {
+                color,
+                custom_size: Some(size),
+                ..default()
+            },
+            transform,
+            ..default()
+        };
+        let Some(path) = texture else {
+            entity.insert(bundle);
+            return;
+        };
+        bundle.texture = assets.load(path.clone());
+        entity.insert((
+            bundle,
+            ImageScaleMode::Tiled {
+                tile_x: true,
+                tile_y: true,
+                stretch_value: 1.,
+            },
+        ));
+    }
+}
+
+/// Size of a shape as far as it can be seen. Borders reach far past the
+/// arena so the ball can't slip around them, but only the part on screen is drawn.
+fn visible_size(shape: &Shape) -> Vec2 {
+    let size = match shape {
+        Shape::Circle { radius } => Vec2::splat(radius * 2.),
+        Shape::Rectangle { width, height } => Vec2::new(*width, *height),
+    };
+    size.min(ARENA_SIZE)
+}
+
+#[derive(Component)]
+struct Net;
+
+fn spawn_background(mut commands: Commands, theme: Res<Theme>, assets: Res<AssetServer>) {
+    theme.insert_sprite(
+        &mut commands.spawn_empty(),
+        &assets,
+        theme.background,
+        &theme.background_texture,
+        ARENA_SIZE,
+        Transform::from_xyz(0., 0., -1.),
+    );
+}
+
+/// Gives the borders a sprite the size of their collision shape. The
+/// transform follows `Position` like for every other shape.
+fn draw_borders(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    assets: Res<AssetServer>,
+    borders: Query<(Entity, &Position, &Shape), Added<Border>>,
+) {
+    for (entity, position, shape) in &borders {
+        theme.insert_sprite(
+            &mut commands.entity(entity),
+            &assets,
+            theme.wall,
+            &theme.wall_texture,
+            visible_size(shape),
+            Transform::from_translation(position.0.extend(0.)),
+        );
+    }
+}
+
+/// Lays the dashed net down the middle, between the inner edges of the top
+/// and bottom borders.
+fn draw_net(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    borders: Query<(&Border, &Position, &Shape), Added<Border>>,
+) {
+    let edge = |side: Border| {
+        borders.iter().find_map(|(border, position, shape)| {
+            let Shape::Rectangle { height, .. } = shape else {
+                return None;
+            };
+            (*border == side).then(|| position.0.y - side.outward().y * height / 2.)
+        })
+    };
+    let (Some(top), Some(bottom)) = (edge(Border::Top), edge(Border::Bottom)) else {
+        return;
+    };
+
+    let step = theme.net_dash + theme.net_gap;
+    if step <= 0. {
+        return;
+    }
+    let mut y = bottom + theme.net_gap / 2.;
+    while y < top {
+        let length = theme.net_dash.min(top - y);
+        commands.spawn((
+            Net,
+            SpriteBundle {
+                sprite: Sprite {
+                    color: theme.net,
+                    custom_size: Some(Vec2::new(theme.net_width, length)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(0., y + length / 2., -0.5),
+                ..default()
+            },
+        ));
+        y += step;
+    }
+}
+
+/// Highlights the borders that are somebody's goal in this match.
+fn color_goals(theme: Res<Theme>, mut borders: Query<(&mut Sprite, Has<TeamGoal>), With<Border>>) {
+    for (mut sprite, is_goal) in &mut borders {
+        let color = if is_goal { theme.goal } else { theme.wall };
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
+
+/// Draws the court: the background, the walls, the goals and the net.
+pub struct CourtPlugin;
+impl Plugin for CourtPlugin {
+    fn build(&self, app: &mut App) {
+        let theme: Theme = load_ron_or_default(&asset_path(THEME_PATH));
+        app.insert_resource(theme)
+            .add_systems(Startup, spawn_background)
+            .add_systems(Update, (draw_borders, draw_net, color_goals).chain());
+    }
+}
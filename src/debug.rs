@@ -0,0 +1,256 @@
+use bevy::{
+    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    math::bounding::{Aabb2d, BoundingCircle},
+    prelude::*,
+};
+
+use crate::{
+    ball::{Ball, BallCollision, BallTuning, Spin},
+    game_manager::Countdown,
+    settings::Settings,
+    team::TeamGoal,
+    utils::{ball_collision, Position, Shape, Velocity, ARENA_SIZE},
+};
+
+/// How many frames ahead the ball's path is predicted.
+const PREDICTED_FRAMES: usize = 180;
+/// Velocities are drawn this many frames long, as they are tiny per frame.
+const VELOCITY_SCALE: f32 = 8.;
+/// How long a contact point stays on screen, in seconds.
+const CONTACT_LIFETIME: f32 = 1.;
+
+const SHAPE_COLOR: Color = Color::GREEN;
+const VELOCITY_COLOR: Color = Color::YELLOW;
+const CONTACT_COLOR: Color = Color::RED;
+const TRAJECTORY_COLOR: Color = Color::CYAN;
+
+/// Whether collision shapes, velocities and the debug panel are drawn.
+#[derive(States, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DebugOverlay {
+    #[default]
+    Hidden,
+    Shown,
+}
+
+/// Where the ball touched something lately and which way it was pushed out.
+#[derive(Resource, Default)]
+struct RecentContacts(Vec<(Vec2, Vec2, Timer)>);
+
+#[derive(Component)]
+struct DebugPanel;
+
+fn toggle_overlay(
+    keys: Res<ButtonInput<KeyCode>>,
+    settings: Res<Settings>,
+    state: Res<State<DebugOverlay>>,
+    mut next_state: ResMut<NextState<DebugOverlay>>,
+) {
+    if keys.just_pressed(settings.key_bindings.debug) {
+        next_state.set(match state.get() {
+            DebugOverlay::Hidden => DebugOverlay::Shown,
+            DebugOverlay::Shown => DebugOverlay::Hidden,
+        });
+    }
+}
+
+/// Outlines every collision shape. Borders reach far past the arena, so
+/// their outlines are cut short a little outside of it.
+fn draw_shapes(mut gizmos: Gizmos, shapes: Query<(&Position, &Shape)>) {
+    for (position, shape) in &shapes {
+        match shape {
+            Shape::Circle { radius } => {
+                gizmos.circle_2d(position.0, *radius, SHAPE_COLOR);
+            }
+            Shape::Rectangle { width, height } => {
+                let size = Vec2::new(*width, *height).min(ARENA_SIZE * 2.);
+                gizmos.rect_2d(position.0, 0., size, SHAPE_COLOR);
+            }
+        }
+    }
+}
+
+fn draw_velocities(mut gizmos: Gizmos, movers: Query<(&Position, &Velocity)>) {
+    for (position, velocity) in &movers {
+        if velocity.0 != Vec2::ZERO {
+            gizmos.arrow_2d(
+                position.0,
+                position.0 + velocity.0 * VELOCITY_SCALE,
+                VELOCITY_COLOR,
+            );
+        }
+    }
+}
+
+/// Remembers the contacts of this frame. Runs while hidden too, so the
+/// overlay shows the last second as soon as it is turned on.
+fn collect_contacts(
+    mut events: EventReader<BallCollision>,
+    ball: Query<(&Position, &Shape), With<Ball>>,
+    mut contacts: ResMut<RecentContacts>,
+    time: Res<Time>,
+) {
+    for (_, _, timer) in &mut contacts.0 {
+        timer.tick(time.delta());
+    }
+    contacts.0.retain(|(_, _, timer)| !timer.finished());
+
+    let Ok((position, Shape::Circle { radius })) = ball.get_single() else {
+        events.clear();
+        return;
+    };
+    for event in events.read() {
+        let normal = event.contact.normal;
+        contacts.0.push((
+            position.0 - normal * *radius,
+            normal,
+            Timer::from_seconds(CONTACT_LIFETIME, TimerMode::Once),
+        ));
+    }
+}
+
+fn draw_contacts(mut gizmos: Gizmos, contacts: Res<RecentContacts>) {
+    for (point, normal, _) in &contacts.0 {
+        gizmos.circle_2d(*point, 4., CONTACT_COLOR);
+        gizmos.line_2d(*point, *point + *normal * 20., CONTACT_COLOR);
+    }
+}
+
+/// Follows the ball the way `ball.rs` moves it, against everything as it
+/// stands now, until it reaches a goal. Paddles are taken as standing still.
+fn draw_trajectory(
+    mut gizmos: Gizmos,
+    ball: Query<(&Position, &Velocity, &Shape, &Spin), With<Ball>>,
+    world: Query<(&Position, &Shape, Has<TeamGoal>), Without<Ball>>,
+    tuning: Res<BallTuning>,
+) {
+    let Ok((position, velocity, Shape::Circle { radius }, spin)) = ball.get_single() else {
+        return;
+    };
+    let mut position = position.0;
+    let mut velocity = velocity.0;
+    let mut rate = spin.rate;
+    if velocity == Vec2::ZERO {
+        return;
+    }
+
+    let mut path = vec![position];
+    'frames: for _ in 0..PREDICTED_FRAMES {
+        velocity = Vec2::from_angle(tuning.magnus * rate).rotate(velocity);
+        rate *= tuning.spin_decay;
+        position += velocity;
+
+        for (wall_position, shape, is_goal) in &world {
+            let Shape::Rectangle { width, height } = shape else {
+                continue;
+            };
+            let Some(contact) = ball_collision(
+                BoundingCircle::new(position, *radius),
+                Aabb2d::new(wall_position.0, Vec2::new(*width, *height) / 2.),
+            ) else {
+                continue;
+            };
+            if is_goal {
+                path.push(position);
+                break 'frames;
+            }
+            position += contact.normal * contact.penetration;
+            let approach = velocity.dot(contact.normal);
+            if approach < 0. {
+                velocity -= 2. * approach * contact.normal;
+            }
+        }
+        path.push(position);
+    }
+    gizmos.linestrip_2d(path, TRAJECTORY_COLOR);
+}
+
+fn spawn_panel(mut commands: Commands) {
+    commands.spawn((
+        DebugPanel,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 18.,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.),
+            left: Val::Px(8.),
+            ..default()
+        })
+        .with_background_color(Color::rgba(0., 0., 0., 0.6)),
+    ));
+}
+
+fn despawn_panel(mut commands: Commands, panel: Query<Entity, With<DebugPanel>>) {
+    for entity in &panel {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn update_panel(
+    mut panel: Query<&mut Text, With<DebugPanel>>,
+    diagnostics: Res<DiagnosticsStore>,
+    entities: Query<Entity>,
+    ball: Query<&Velocity, With<Ball>>,
+    countdown: Query<&Countdown>,
+) {
+    let Ok(mut text) = panel.get_single_mut() else {
+        return;
+    };
+    let fps = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed())
+        .map_or("-".to_string(), |fps| format!("{:.0}", fps));
+    let speed = ball.get_single().map_or("-".to_string(), |velocity| {
+        format!("{:.2}", velocity.0.length())
+    });
+    let countdown = countdown
+        .get_single()
+        .map_or("off".to_string(), |countdown| {
+            format!("{:.1}s left", countdown.timer.remaining_secs())
+        });
+
+    text.sections[0].value = format!(
+        "FPS: {}\nEntities: {}\nBall speed: {} per frame\nCountdown: {}",
+        fps,
+        entities.iter().count(),
+        speed,
+        countdown
+    );
+}
+
+/// Draws the collision shapes, velocities, recent contacts and the predicted
+/// path of the ball over the game, with a panel of numbers in the corner.
+/// Toggled with the debug key, F3 by default.
+pub struct DebugPlugin;
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugins(FrameTimeDiagnosticsPlugin);
+        }
+        app.init_state::<DebugOverlay>()
+            .init_resource::<RecentContacts>()
+            .add_systems(OnEnter(DebugOverlay::Shown), spawn_panel)
+            .add_systems(OnExit(DebugOverlay::Shown), despawn_panel)
+            .add_systems(
+                Update,
+                (
+                    toggle_overlay,
+                    collect_contacts,
+                    (
+                        draw_shapes,
+                        draw_velocities,
+                        draw_contacts,
+                        draw_trajectory,
+                        update_panel,
+                    )
+                        .after(collect_contacts)
+                        .run_if(in_state(DebugOverlay::Shown)),
+                ),
+            );
+    }
+}
@@ -0,0 +1,498 @@
+//! The game as a reinforcement learning environment. An agent moves the right
+//! paddle against a bot on the left, one frame per step, without a window:
+//!
+//! ```no_run
+//! use bevy_pong::{bot::MoveCommand, env::{EnvConfig, PongEnv}};
+//!
+//! let mut env = PongEnv::new(&EnvConfig::default()).unwrap();
+//! let mut observation = env.reset(7);
+//! loop {
+//!     let toward_ball = (observation.ball_position - observation.paddle.position)
+//!         .dot(observation.paddle.rail);
+//!     let (next, _reward, done) = env.step(MoveCommand(toward_ball.signum()));
+//!     observation = next;
+//!     if done {
+//!         break;
+//!     }
+//! }
+//! ```
+//!
+//! Every environment is an `App` of its own, so any number of them can be
+//! stepped at once, see `VecEnv`.
+
+use std::{
+    num::NonZeroUsize,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex, PoisonError,
+    },
+    thread::{self, JoinHandle},
+};
+
+use bevy::{
+    app::MainScheduleOrder,
+    ecs::{event::ManualEventReader, schedule::ExecutorKind, system::SystemState},
+    prelude::*,
+};
+
+use crate::{
+    add_game_plugins,
+    ball::BallCollision,
+    border::Border,
+    bot::{BotRegistry, MoveCommand, Observation, PaddleController, RegisterBot, DEFAULT_BOT},
+    game_manager::{GameMode, GameState, MatchFinished, MatchSetup, Scored},
+    headless::HeadlessPlugin,
+    level::Level,
+    paddle::{Goal, Observer, Paddle},
+    rng::GameRng,
+    settings::{Difficulty, Settings, SettingsPlugin},
+    team::TeamId,
+};
+
+/// How fast to move the agent's paddle along its rail, from -1 to 1 of its
+/// full speed, the same as a bot's move.
+pub type Action = MoveCommand;
+
+/// Name the agent's paddle is registered under with the bots.
+const AGENT_BOT: &str = "agent";
+
+/// What each event is worth to the agent.
+#[derive(Clone, Copy, Debug)]
+pub struct RewardWeights {
+    /// The ball got past the opponent.
+    pub point: f32,
+    /// The ball got past the agent.
+    pub conceded: f32,
+    /// The agent's paddle hit the ball.
+    pub hit: f32,
+}
+
+impl Default for RewardWeights {
+    fn default() -> Self {
+        RewardWeights {
+            point: 1.,
+            conceded: -1.,
+            hit: 0.1,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EnvConfig {
+    /// Bot on the left paddle.
+    pub opponent: String,
+    /// How fast both paddles move.
+    pub difficulty: Difficulty,
+    /// Points that end the episode.
+    pub target_score: u32,
+    pub rewards: RewardWeights,
+    /// Frames after which the episode ends however the match stands.
+    pub max_frames: Option<u32>,
+    pub level: Level,
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        EnvConfig {
+            opponent: DEFAULT_BOT.to_string(),
+            difficulty: Difficulty::default(),
+            target_score: 5,
+            rewards: RewardWeights::default(),
+            // ten minutes at 60 frames per second
+            max_frames: Some(36_000),
+            level: Level::default(),
+        }
+    }
+}
+
+/// Moves the agent's paddle as the last step asked.
+struct Agent(Arc<Mutex<Action>>);
+impl PaddleController for Agent {
+    fn act(&mut self, _: &Observation) -> MoveCommand {
+        *self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// One match at a time between the agent and a bot.
+pub struct PongEnv {
+    app: App,
+    action: Arc<Mutex<Action>>,
+    observer: SystemState<Observer<'static, 'static>>,
+    scored: ManualEventReader<Scored>,
+    collisions: ManualEventReader<BallCollision>,
+    finished: ManualEventReader<MatchFinished>,
+    rewards: RewardWeights,
+    max_frames: Option<u32>,
+    /// The agent's paddle and team in the current episode.
+    paddle: Entity,
+    team: TeamId,
+    /// Last seen with the ball on the court, there is one from the reset on.
+    observation: Option<Observation>,
+    frames: u32,
+    done: bool,
+}
+
+impl PongEnv {
+    /// An environment reset with seed 0. Fails on an opponent that isn't a
+    /// built-in bot.
+    pub fn new(config: &EnvConfig) -> anyhow::Result<Self> {
+        let settings = Settings {
+            difficulty: config.difficulty,
+            target_score: config.target_score,
+            ..default()
+        };
+        let mut setup = MatchSetup::from_settings(&settings).with_mode(GameMode::AiVsAi);
+        setup.bots[0] = AGENT_BOT.to_string();
+        setup.bots[1].clone_from(&config.opponent);
+
+        let mut app = App::new();
+        // wait in the menu until the first episode is set up
+        app.add_plugins(HeadlessPlugin)
+            .insert_state(GameState::Menu);
+        add_game_plugins(
+            &mut app,
+            SettingsPlugin::new(settings),
+            setup,
+            config.level.clone(),
+        );
+        let action = Arc::new(Mutex::new(Action::default()));
+        let shared = action.clone();
+        app.register_bot(AGENT_BOT, move || Agent(shared.clone()));
+        app.world
+            .resource::<BotRegistry>()
+            .check_names(std::slice::from_ref(&config.opponent))?;
+
+        // environments are stepped on threads of their own already, running
+        // the systems of a frame in parallel too would only get in the way
+        let labels = app.world.resource::<MainScheduleOrder>().labels.clone();
+        for label in labels {
+            app.edit_schedule(label, |schedule| {
+                schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+            });
+        }
+        app.finish();
+        app.cleanup();
+        app.update();
+
+        let observer = SystemState::new(&mut app.world);
+        let mut env = PongEnv {
+            app,
+            action,
+            observer,
+            scored: ManualEventReader::default(),
+            collisions: ManualEventReader::default(),
+            finished: ManualEventReader::default(),
+            rewards: config.rewards,
+            max_frames: config.max_frames,
+            paddle: Entity::PLACEHOLDER,
+            team: TeamId(0),
+            observation: None,
+            frames: 0,
+            done: true,
+        };
+        env.reset(0);
+        Ok(env)
+    }
+
+    /// Starts a new match, served from `seed`, and returns what the agent sees.
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        let world = &mut self.app.world;
+        if *world.resource::<State<GameState>>().get() != GameState::Menu {
+            // the menu takes the paddles off the court
+            world
+                .resource_mut::<NextState<GameState>>()
+                .set(GameState::Menu);
+            self.app.update();
+        }
+
+        let world = &mut self.app.world;
+        world.resource_mut::<MatchSetup>().seed = seed;
+        world.insert_resource(GameRng::new(seed));
+        *self.action.lock().unwrap_or_else(PoisonError::into_inner) = Action::default();
+        world
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::Playing);
+        self.app.update();
+
+        let world = &mut self.app.world;
+        (self.paddle, self.team) = world
+            .query_filtered::<(Entity, &Goal, &TeamId), With<Paddle>>()
+            .iter(world)
+            .find(|(_, goal, _)| goal.0 == Border::Right)
+            .map(|(entity, _, team)| (entity, *team))
+            .expect("the agent's paddle is on the court");
+        // nothing from the last episode counts
+        self.scored.clear(world.resource::<Events<Scored>>());
+        self.collisions
+            .clear(world.resource::<Events<BallCollision>>());
+        self.finished
+            .clear(world.resource::<Events<MatchFinished>>());
+        self.frames = 0;
+        self.done = false;
+        self.observe()
+    }
+
+    /// Moves the agent's paddle as `action` asks for a frame. Returns what
+    /// the agent sees then, its reward for the frame and whether the episode
+    /// is over. Once it is, steps change nothing until the next `reset`.
+    pub fn step(&mut self, action: Action) -> (Observation, f32, bool) {
+        if self.done {
+            return (self.observe(), 0., true);
+        }
+
+        *self.action.lock().unwrap_or_else(PoisonError::into_inner) = action;
+        self.app.update();
+        self.frames += 1;
+
+        let world = &self.app.world;
+        let mut reward = 0.;
+        for event in self.scored.read(world.resource::<Events<Scored>>()) {
+            reward += if event.against == self.team {
+                self.rewards.conceded
+            } else {
+                self.rewards.point
+            };
+        }
+        for event in self
+            .collisions
+            .read(world.resource::<Events<BallCollision>>())
+        {
+            if event.entity == self.paddle {
+                reward += self.rewards.hit;
+            }
+        }
+        let finished = self
+            .finished
+            .read(world.resource::<Events<MatchFinished>>())
+            .count()
+            > 0;
+        self.done = finished || self.max_frames.is_some_and(|max| self.frames >= max);
+
+        (self.observe(), reward, self.done)
+    }
+
+    /// Frames played in the current episode.
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    /// What the agent sees now, or saw last while the ball was off the court.
+    fn observe(&mut self) -> Observation {
+        let observer = self.observer.get(&self.app.world);
+        if let Some(observation) = observer.observe(self.paddle) {
+            self.observation = Some(observation);
+        }
+        self.observation
+            .clone()
+            .expect("the ball is on the court when the match starts")
+    }
+}
+
+/// What an environment returns for a step: what the agent sees, its reward
+/// and whether the episode is over.
+type Transition = (Observation, f32, bool);
+
+/// What a worker is asked to do with its environments.
+enum Job {
+    /// Reset them all, seeded by their index in the `VecEnv` on top of this.
+    Reset(u64),
+    /// Reset the one at this index among the worker's.
+    ResetOne(usize, u64),
+    /// Step every one with its action.
+    Step(Vec<Action>),
+}
+
+/// A thread that owns a run of the environments, from `first` on, and works
+/// through the jobs sent to it.
+struct Worker {
+    jobs: Sender<Job>,
+    results: Receiver<Vec<Transition>>,
+    thread: JoinHandle<()>,
+}
+
+impl Worker {
+    fn spawn(first: usize, mut envs: Vec<PongEnv>) -> Self {
+        let (jobs, queue) = mpsc::channel();
+        let (sender, results) = mpsc::channel();
+        let thread = thread::spawn(move || {
+            for job in queue {
+                let transitions = match job {
+                    Job::Reset(seed) => envs
+                        .iter_mut()
+                        .enumerate()
+                        .map(|(offset, env)| {
+                            let seed = seed.wrapping_add((first + offset) as u64);
+                            (env.reset(seed), 0., false)
+                        })
+                        .collect(),
+                    Job::ResetOne(index, seed) => vec![(envs[index].reset(seed), 0., false)],
+                    Job::Step(actions) => envs
+                        .iter_mut()
+                        .zip(actions)
+                        .map(|(env, action)| env.step(action))
+                        .collect(),
+                };
+                if sender.send(transitions).is_err() {
+                    break;
+                }
+            }
+        });
+        Worker {
+            jobs,
+            results,
+            thread,
+        }
+    }
+
+    fn send(&self, job: Job) {
+        self.jobs.send(job).expect("an environment panicked");
+    }
+
+    fn receive(&self) -> Vec<Transition> {
+        self.results.recv().expect("an environment panicked")
+    }
+}
+
+/// Environments stepped together, spread over the cores of the machine. Each
+/// core runs a worker thread for as long as the `VecEnv` lives.
+pub struct VecEnv {
+    workers: Vec<Worker>,
+    /// Environments per worker, the last one can have fewer.
+    chunk: usize,
+    len: usize,
+}
+
+impl VecEnv {
+    pub fn new(count: usize, config: &EnvConfig) -> anyhow::Result<Self> {
+        let mut envs = (0..count)
+            .map(|_| PongEnv::new(config))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+        let chunk = count.div_ceil(threads).max(1);
+        let mut workers = Vec::new();
+        while !envs.is_empty() {
+            let rest = envs.split_off(chunk.min(envs.len()));
+            workers.push(Worker::spawn(workers.len() * chunk, envs));
+            envs = rest;
+        }
+        Ok(VecEnv {
+            workers,
+            chunk,
+            len: count,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Resets every environment, the first with `seed`, the next with
+    /// `seed + 1` and so on.
+    pub fn reset(&mut self, seed: u64) -> Vec<Observation> {
+        for worker in &self.workers {
+            worker.send(Job::Reset(seed));
+        }
+        self.collect()
+            .into_iter()
+            .map(|(observation, _, _)| observation)
+            .collect()
+    }
+
+    /// Resets a single environment, typically one whose episode is over.
+    pub fn reset_one(&mut self, index: usize, seed: u64) -> Observation {
+        assert!(index < self.len, "no environment {}", index);
+        let worker = &self.workers[index / self.chunk];
+        worker.send(Job::ResetOne(index % self.chunk, seed));
+        let (observation, _, _) = worker.receive().remove(0);
+        observation
+    }
+
+    /// Steps every environment with the action at the same index.
+    pub fn step(&mut self, actions: &[Action]) -> Vec<(Observation, f32, bool)> {
+        assert_eq!(actions.len(), self.len, "one action per environment");
+        for (worker, actions) in self.workers.iter().zip(actions.chunks(self.chunk)) {
+            worker.send(Job::Step(actions.to_vec()));
+        }
+        self.collect()
+    }
+
+    /// The answers of every worker to the job they were just sent, in the
+    /// order of the environments.
+    fn collect(&self) -> Vec<Transition> {
+        self.workers.iter().flat_map(Worker::receive).collect()
+    }
+}
+
+impl Drop for VecEnv {
+    fn drop(&mut self) {
+        for worker in self.workers.drain(..) {
+            // without jobs coming the worker is done
+            drop(worker.jobs);
+            _ = worker.thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Frames long enough for a few points to be played.
+    const FRAMES: usize = 1200;
+
+    /// Heads for the ball, but lazily, so the bot on the other side scores too.
+    fn policy(observation: &Observation) -> Action {
+        let paddle = &observation.paddle;
+        let diff = (observation.ball_position - paddle.position).dot(paddle.rail);
+        MoveCommand((diff / 100.).clamp(-1., 1.))
+    }
+
+    fn trajectory(env: &mut PongEnv, seed: u64) -> Vec<(Observation, f32, bool)> {
+        let mut observation = env.reset(seed);
+        (0..FRAMES)
+            .map(|_| {
+                let transition = env.step(policy(&observation));
+                observation = transition.0.clone();
+                transition
+            })
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_plays_the_same_episode() {
+        let config = EnvConfig::default();
+        let mut first = PongEnv::new(&config).unwrap();
+        let mut second = PongEnv::new(&config).unwrap();
+
+        let expected = trajectory(&mut first, 7);
+        assert!(expected.iter().any(|(_, reward, _)| *reward != 0.));
+        assert_eq!(trajectory(&mut second, 7), expected);
+        // and again after an episode was played in the same environment
+        assert_eq!(trajectory(&mut first, 7), expected);
+    }
+
+    #[test]
+    fn vec_env_matches_single_environments() {
+        let config = EnvConfig::default();
+        let mut envs = VecEnv::new(3, &config).unwrap();
+        let mut observations = envs.reset(11);
+        let mut steps = Vec::new();
+        for _ in 0..FRAMES {
+            let actions: Vec<Action> = observations.iter().map(policy).collect();
+            let transitions = envs.step(&actions);
+            observations = transitions.iter().map(|step| step.0.clone()).collect();
+            steps.push(transitions);
+        }
+
+        for index in 0..envs.len() {
+            let mut env = PongEnv::new(&config).unwrap();
+            let expected = trajectory(&mut env, 11 + index as u64);
+            let actual: Vec<_> = steps.iter().map(|step| step[index].clone()).collect();
+            assert_eq!(actual, expected, "environment {}", index);
+        }
+    }
+}
@@ -0,0 +1,348 @@
+//! Lets a program outside the game move a paddle.
+//!
+//! The game and the bot exchange JSON messages, one per line, over the bot's
+//! stdin and stdout or over a TCP connection. Both start with a handshake:
+//!
+//! ```text
+//! game: {"type":"hello","version":1}
+//! bot:  {"type":"hello","version":1,"name":"echo"}
+//! ```
+//!
+//! then every frame the game sends what the bot sees and waits for its move,
+//! from -1 to 1 of the paddle's full speed along its rail:
+//!
+//! ```text
+//! game: {"type":"observation","frame":1,"observation":{...}}
+//! bot:  {"type":"move","frame":1,"command":1.0}
+//! ```
+//!
+//! A bot that misses the deadline keeps its last move, and its answer is
+//! dropped when it comes in late. A bot that stays silent for
+//! `MAX_MISSED_FRAMES` in a row is cut off and its paddle stands still.
+//!
+//! The game connects to a bot when the bot is registered, at launch, and
+//! keeps the connection for every match after, frame numbers carry on across
+//! them. Observations the bot doesn't read in time are dropped.
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{Shutdown, TcpStream},
+    process::{Child, Command, Stdio},
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError},
+        Arc, Mutex, PoisonError,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, Context};
+use bevy::{
+    app::App,
+    log::{error, info, warn},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::bot::{MoveCommand, Observation, PaddleController, RegisterBot};
+
+pub const PROTOCOL_VERSION: u32 = 1;
+/// How long a bot has to answer the handshake, to leave time for it to start.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Three seconds at 60 frames per second.
+const MAX_MISSED_FRAMES: u32 = 180;
+/// Lines waiting to be written to the bot. Newer ones are dropped while the
+/// bot isn't reading, rather than holding up the frame.
+const OUTBOX_SIZE: usize = 1;
+
+/// Messages from the game to the bot.
+#[derive(Serialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ToBot<'a> {
+    Hello {
+        version: u32,
+    },
+    Observation {
+        frame: u64,
+        observation: &'a Observation,
+    },
+}
+
+/// Messages from the bot to the game.
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FromBot {
+    Hello {
+        version: u32,
+        #[serde(default)]
+        name: Option<String>,
+    },
+    Move {
+        frame: u64,
+        command: f32,
+    },
+}
+
+/// The open connection to a bot.
+struct Link {
+    outbox: SyncSender<String>,
+    replies: Receiver<anyhow::Result<FromBot>>,
+    /// The bot's process, when the game started it.
+    child: Option<Child>,
+    /// The socket, when the game connected to the bot. Shutting it down ends
+    /// the reader thread.
+    stream: Option<TcpStream>,
+}
+
+impl Link {
+    /// Starts `spec`, which is either `tcp:<address>` of a bot already
+    /// listening, or a command line, split on whitespace, to run the bot.
+    fn open(spec: &str) -> anyhow::Result<Self> {
+        let (reader, mut writer, child, stream): (
+            Box<dyn BufRead + Send>,
+            Box<dyn Write + Send>,
+            _,
+            _,
+        ) = match spec.strip_prefix("tcp:") {
+            Some(address) => {
+                let stream = TcpStream::connect(address)
+                    .with_context(|| format!("could not connect to {}", address))?;
+                stream.set_nodelay(true)?;
+                let reader = BufReader::new(stream.try_clone()?);
+                let writer = stream.try_clone()?;
+                (Box::new(reader), Box::new(writer), None, Some(stream))
+            }
+            None => {
+                let mut words = spec.split_whitespace();
+                let program = words
+                    .next()
+                    .ok_or_else(|| anyhow!("the command is empty"))?;
+                let mut child = Command::new(program)
+                    .args(words)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .spawn()
+                    .with_context(|| format!("could not run `{}`", spec))?;
+                let stdin = child.stdin.take().expect("stdin is piped");
+                let stdout = child.stdout.take().expect("stdout is piped");
+                (
+                    Box::new(BufReader::new(stdout)),
+                    Box::new(stdin),
+                    Some(child),
+                    None,
+                )
+            }
+        };
+
+        // read and write on threads of their own so a slow bot can't hold up
+        // the frame
+        let (sender, replies) = mpsc::channel();
+        thread::spawn(move || {
+            for line in reader.lines() {
+                let reply = line
+                    .map_err(anyhow::Error::from)
+                    .and_then(|line| Ok(serde_json::from_str(&line)?));
+                if sender.send(reply).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let (outbox, lines) = mpsc::sync_channel::<String>(OUTBOX_SIZE);
+        thread::spawn(move || {
+            for line in lines {
+                if writeln!(writer, "{}", line)
+                    .and_then(|_| writer.flush())
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        let link = Link {
+            outbox,
+            replies,
+            child,
+            stream,
+        };
+        link.handshake()?;
+        Ok(link)
+    }
+
+    /// Queues `message` for the writer thread, dropping it when the bot is
+    /// behind on reading.
+    fn send(&self, message: &ToBot) -> anyhow::Result<()> {
+        let line = serde_json::to_string(message)?;
+        match self.outbox.try_send(line) {
+            Ok(()) | Err(TrySendError::Full(_)) => Ok(()),
+            Err(TrySendError::Disconnected(_)) => bail!("the bot stopped reading"),
+        }
+    }
+
+    fn receive(&self, timeout: Duration) -> anyhow::Result<Option<FromBot>> {
+        match self.replies.recv_timeout(timeout) {
+            Ok(reply) => reply.map(Some),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => bail!("the bot closed the connection"),
+        }
+    }
+
+    fn handshake(&self) -> anyhow::Result<()> {
+        self.send(&ToBot::Hello {
+            version: PROTOCOL_VERSION,
+        })?;
+        match self.receive(HANDSHAKE_TIMEOUT)? {
+            Some(FromBot::Hello { version, name }) if version == PROTOCOL_VERSION => {
+                info!(
+                    "bot {} connected",
+                    name.as_deref().unwrap_or("without a name")
+                );
+                Ok(())
+            }
+            Some(FromBot::Hello { version, .. }) => bail!(
+                "the bot speaks version {} of the protocol, the game {}",
+                version,
+                PROTOCOL_VERSION
+            ),
+            Some(other) => bail!("expected a hello, got {:?}", other),
+            None => bail!("no hello within {:?}", HANDSHAKE_TIMEOUT),
+        }
+    }
+
+    /// Sends the observation for `frame` and waits for the move, until the
+    /// deadline. Answers to earlier frames are skipped.
+    fn exchange(
+        &self,
+        frame: u64,
+        observation: &Observation,
+        timeout: Duration,
+    ) -> anyhow::Result<Option<MoveCommand>> {
+        self.send(&ToBot::Observation { frame, observation })?;
+        let deadline = Instant::now() + timeout;
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            match self.receive(left)? {
+                Some(FromBot::Move {
+                    frame: answered,
+                    command,
+                }) if answered == frame => return Ok(Some(MoveCommand(command))),
+                Some(FromBot::Move { .. }) => continue,
+                Some(other) => bail!("expected a move, got {:?}", other),
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+impl Drop for Link {
+    fn drop(&mut self) {
+        if let Some(child) = &mut self.child {
+            _ = child.kill();
+            _ = child.wait();
+        }
+        if let Some(stream) = &self.stream {
+            _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+/// The connection to a bot, shared by every paddle it drives.
+struct Connection {
+    spec: String,
+    /// None once the bot couldn't be reached or was cut off. It isn't tried
+    /// again.
+    link: Option<Link>,
+    /// The last frame sent, counted across matches so an answer left over
+    /// from the match before is dropped.
+    frame: u64,
+}
+
+impl Connection {
+    /// Connects to the bot right away, see `Link::open` for the spec.
+    fn open(spec: &str) -> Self {
+        let link = Link::open(spec)
+            .map_err(|err| error!("bot `{}` is not playing: {:#}", spec, err))
+            .ok();
+        Connection {
+            spec: spec.to_string(),
+            link,
+            frame: 0,
+        }
+    }
+}
+
+/// A paddle controller that asks another program for every move.
+pub struct ExternalBot {
+    connection: Arc<Mutex<Connection>>,
+    timeout: Duration,
+    last: MoveCommand,
+    missed: u32,
+}
+
+impl ExternalBot {
+    /// A bot driven by the program `spec` points to, see `Link::open`. It is
+    /// connected to right away. A bot that can't be reached leaves its paddle
+    /// standing still.
+    pub fn new(spec: &str, timeout: Duration) -> Self {
+        ExternalBot::shared(Arc::new(Mutex::new(Connection::open(spec))), timeout)
+    }
+
+    fn shared(connection: Arc<Mutex<Connection>>, timeout: Duration) -> Self {
+        ExternalBot {
+            connection,
+            timeout,
+            last: MoveCommand::default(),
+            missed: 0,
+        }
+    }
+}
+
+impl PaddleController for ExternalBot {
+    fn act(&mut self, observation: &Observation) -> MoveCommand {
+        let mut connection = self
+            .connection
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        connection.frame += 1;
+        let frame = connection.frame;
+        let Some(link) = &connection.link else {
+            return MoveCommand::default();
+        };
+
+        let cut_off = match link.exchange(frame, observation, self.timeout) {
+            Ok(Some(command)) => {
+                self.last = command;
+                self.missed = 0;
+                None
+            }
+            Ok(None) => {
+                self.missed += 1;
+                if self.missed == 1 {
+                    warn!("bot `{}` is late, keeping its last move", connection.spec);
+                }
+                (self.missed >= MAX_MISSED_FRAMES)
+                    .then(|| anyhow!("no answer for {} frames", self.missed))
+            }
+            Err(err) => Some(err),
+        };
+        if let Some(reason) = cut_off {
+            error!("bot `{}` is cut off: {:#}", connection.spec, reason);
+            connection.link = None;
+            self.last = MoveCommand::default();
+        }
+        self.last
+    }
+}
+
+/// Registers a bot for every `(name, command)` pair, each given `timeout` to
+/// answer every frame. The bots are connected to here, so the handshake
+/// doesn't hold up a frame, and every match a bot plays goes over the same
+/// connection.
+pub fn register_external_bots(app: &mut App, bots: &[(String, String)], timeout: Duration) {
+    for (name, command) in bots {
+        let connection = Arc::new(Mutex::new(Connection::open(command)));
+        app.register_bot(name, move || {
+            ExternalBot::shared(connection.clone(), timeout)
+        });
+    }
+}
@@ -1,43 +1,273 @@
-use std::time::Duration;
+use std::{collections::HashMap, time::Duration};
 
+use anyhow::{anyhow, bail};
 use bevy::{
-    app::{Plugin, Startup, Update},
+    app::{Plugin, Update},
     ecs::{
         component::Component,
         entity::Entity,
         event::{Event, EventReader, EventWriter},
-        query::With,
-        system::{Commands, Query, Res, ResMut, Resource},
+        schedule::{
+            common_conditions::{in_state, not, resource_changed, resource_exists},
+            Condition, IntoSystemConfigs, IntoSystemSetConfigs, NextState, OnEnter, OnExit,
+            OnTransition, State, States, SystemSet,
+        },
+        system::{Commands, Local, Query, Res, ResMut, Resource},
+        world::World,
     },
-    time::{Time, Timer, TimerMode},
+    time::{Time, Timer, TimerMode, Virtual},
 };
 
+use rand::Rng;
+
 use crate::{
     ball::BallCollision,
-    border::{self, Border},
-    utils::Position,
+    bot::DEFAULT_BOT,
+    clock::TimedMatch,
+    console::{ConsoleCommand, RegisterCommand},
+    paddle::Controller,
+    rng::GameRng,
+    serve::{ServeMode, ServeRule},
+    settings::{Difficulty, Settings},
+    team::{TeamGoal, TeamId, Teams, WinRule},
+    time_scale::{simulation_running, TimeScale},
 };
 
-#[derive(Event)]
-pub enum Scored {
-    Player,
-    Ai,
+/// Sent when the ball gets into a team's goal.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scored {
+    pub against: TeamId,
 }
 
-#[derive(Resource, Default)]
+/// Points and goals let in, by team.
+#[derive(Resource, Default, Clone, Debug)]
 pub struct Score {
-    pub player: u32,
-    pub ai: u32,
+    pub points: HashMap<TeamId, u32>,
+    pub conceded: HashMap<TeamId, u32>,
+}
+
+impl Score {
+    pub fn points(&self, team: TeamId) -> u32 {
+        self.points.get(&team).copied().unwrap_or_default()
+    }
+
+    pub fn conceded(&self, team: TeamId) -> u32 {
+        self.conceded.get(&team).copied().unwrap_or_default()
+    }
+
+    /// Counts a goal against `against`, which is a point for every other team.
+    fn goal(&mut self, against: TeamId, teams: &Teams) {
+        *self.conceded.entry(against).or_default() += 1;
+        for (team, _) in teams.iter().filter(|(team, _)| *team != against) {
+            *self.points.entry(team).or_default() += 1;
+        }
+    }
+
+    /// Whether `team` has been knocked out of a `WinRule::LastStanding` match.
+    pub fn is_out(&self, team: TeamId, teams: &Teams, target_score: u32) -> bool {
+        teams.rule == WinRule::LastStanding && self.conceded(team) >= target_score
+    }
+
+    /// Teams that are not out, in registry order.
+    pub fn in_play(&self, teams: &Teams, target_score: u32) -> Vec<TeamId> {
+        teams
+            .iter()
+            .map(|(team, _)| team)
+            .filter(|team| !self.is_out(*team, teams, target_score))
+            .collect()
+    }
+
+    /// The team that won the match, once there is one.
+    pub fn winner(&self, teams: &Teams, target_score: u32) -> Option<TeamId> {
+        match teams.rule {
+            WinRule::FirstTo => teams
+                .iter()
+                .map(|(team, _)| team)
+                .find(|team| self.points(*team) >= target_score),
+            WinRule::LastStanding => match self.in_play(teams, target_score)[..] {
+                [last] => Some(last),
+                _ => None,
+            },
+            WinRule::Endless => None,
+        }
+    }
+
+    /// The team alone in the lead, among those still in the match.
+    pub fn leader(&self, teams: &Teams, target_score: u32) -> Option<TeamId> {
+        let in_play = self.in_play(teams, target_score);
+        let best = in_play.iter().map(|team| self.points(*team)).max()?;
+        match in_play
+            .into_iter()
+            .filter(|team| self.points(*team) == best)
+            .collect::<Vec<_>>()[..]
+        {
+            [leader] => Some(leader),
+            _ => None,
+        }
+    }
+
+    /// The number that counts for `team`: its points, or the goals it let in
+    /// when those decide the match.
+    pub fn counted(&self, team: TeamId, teams: &Teams) -> u32 {
+        match teams.rule {
+            WinRule::FirstTo => self.points(team),
+            WinRule::LastStanding | WinRule::Endless => self.conceded(team),
+        }
+    }
+
+    /// What the scoreboard shows for `team`: its points, or the goals it let in
+    /// when being knocked out is what counts or there is nobody to score them.
+    pub fn display(&self, team: TeamId, teams: &Teams, target_score: u32) -> String {
+        match teams.rule {
+            WinRule::FirstTo => self.points(team).to_string(),
+            WinRule::LastStanding if self.is_out(team, teams, target_score) => "out".to_string(),
+            WinRule::LastStanding | WinRule::Endless => self.conceded(team).to_string(),
+        }
+    }
+
+    /// Every team with its score, e.g. `ai 3 - 5 you`.
+    pub fn summary(&self, teams: &Teams, target_score: u32) -> String {
+        teams
+            .iter()
+            .map(|(id, team)| match teams.rule {
+                WinRule::FirstTo => format!("{} {}", team.name, self.points(id)),
+                WinRule::LastStanding | WinRule::Endless => {
+                    format!("{} {}", team.name, self.display(id, teams, target_score))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" - ")
+    }
+}
+
+/// Sent when a team has won the match.
+#[derive(Event)]
+pub struct MatchFinished {
+    pub final_score: Score,
+    pub winner: TeamId,
+}
+
+/// `MatchOver` holds the final score on screen until the next match is started.
+/// Every time `Playing` is entered a new match begins.
+#[derive(States, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameState {
+    /// The main menu, where the match is picked.
+    Menu,
+    #[default]
+    Playing,
+    MatchOver,
+}
+
+/// Abandons the match in progress and starts a new one.
+#[derive(Event)]
+pub struct RestartMatch;
+
+/// Present while a match is being restarted, which passes through `MatchOver`
+/// without showing the summary.
+#[derive(Resource)]
+pub struct Restarting;
+
+/// Who controls the paddles.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GameMode {
+    #[default]
+    VsAi,
+    LocalTwoPlayer,
+    AiVsAi,
+    /// One player against the far wall, with no end.
+    Practice,
+    /// A paddle on every side, controlled as listed in `MatchSetup::seats`.
+    Quadrapong,
+}
+
+/// Options of the current match, taken from the settings unless overridden at launch.
+#[derive(Resource, Debug, Clone)]
+pub struct MatchSetup {
+    pub mode: GameMode,
+    pub difficulty: Difficulty,
+    pub target_score: u32,
+    /// Seed of the first match, the following ones are seeded from it.
+    pub seed: u64,
+    pub serve_mode: ServeMode,
+    pub serve_rule: ServeRule,
+    /// Play against the clock instead of up to the target score.
+    pub timed: Option<TimedMatch>,
+    /// Controllers of the right, left, top and bottom paddles in Quadrapong.
+    pub seats: [Controller; 4],
+    /// Bots driving the right, left, top and bottom paddles when the AI has them.
+    pub bots: [String; 4],
+}
+
+impl MatchSetup {
+    pub fn from_settings(settings: &Settings) -> Self {
+        MatchSetup {
+            mode: GameMode::default(),
+            difficulty: settings.difficulty,
+            target_score: settings.target_score,
+            seed: settings.seed.unwrap_or_else(rand::random),
+            serve_mode: settings.serve_mode,
+            serve_rule: settings.serve_rule,
+            timed: settings.timed,
+            seats: [
+                Controller::FirstPlayer,
+                Controller::Ai,
+                Controller::Ai,
+                Controller::Ai,
+            ],
+            bots: [DEFAULT_BOT; 4].map(str::to_string),
+        }
+    }
+
+    /// This setup for a match in `mode`, leaving out what the mode can't do.
+    pub fn with_mode(&self, mode: GameMode) -> Self {
+        let mut setup = MatchSetup {
+            mode,
+            ..self.clone()
+        };
+        match mode {
+            // manual serves are made from the left or right paddle
+            GameMode::Quadrapong => setup.serve_mode = ServeMode::Countdown,
+            // practice never ends, so there is nothing for a clock to decide
+            GameMode::Practice => setup.timed = None,
+            GameMode::VsAi | GameMode::LocalTwoPlayer | GameMode::AiVsAi => {}
+        }
+        setup
+    }
+}
+
+/// Run condition for the countdown that releases the ball after every point.
+pub fn countdown_serves(setup: Res<MatchSetup>) -> bool {
+    setup.serve_mode == ServeMode::Countdown
+}
+
+/// Run condition for the four-player mode.
+pub fn quadrapong(setup: Res<MatchSetup>) -> bool {
+    setup.mode == GameMode::Quadrapong
+}
+
+/// Run condition that is false while the game is paused.
+pub fn unpaused(time: Res<Time<Virtual>>) -> bool {
+    !time.is_paused()
 }
 
+/// Systems that advance the match. They stand still in the menu, and replay
+/// playback turns them off and drives the entities from the recording instead.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GameplaySet;
+
 #[derive(Component)]
 pub struct Countdown {
     pub timer: Timer,
 }
 
-fn count(mut commands: Commands, mut query: Query<(&mut Countdown, Entity)>, time: Res<Time>) {
+fn count(
+    mut commands: Commands,
+    mut query: Query<(&mut Countdown, Entity)>,
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+) {
     for (mut countdown, entity) in &mut query {
-        countdown.timer.tick(time.delta());
+        countdown.timer.tick(time_scale.scaled(time.delta()));
         if countdown.timer.finished() {
             commands.entity(entity).despawn();
         }
@@ -45,32 +275,72 @@ fn count(mut commands: Commands, mut query: Query<(&mut Countdown, Entity)>, tim
 }
 
 fn detect_scoring(
-    borders: Query<&Border, With<Border>>,
+    goals: Query<&TeamGoal>,
     mut events: EventReader<BallCollision>,
     mut events_writer: EventWriter<Scored>,
     mut score: ResMut<Score>,
+    teams: Res<Teams>,
+    setup: Res<MatchSetup>,
 ) {
     for event in events.read() {
-        if let Ok(border) = borders.get(event.entity) {
-            match border {
-                Border::Left => {
-                    events_writer.send(Scored::Ai);
-                    score.ai += 1;
-                }
-                Border::Right => {
-                    events_writer.send(Scored::Player);
-                    score.player += 1;
-                }
-                Border::Top => {}
-                Border::Bottom => {}
-            };
+        let Ok(TeamGoal(team)) = goals.get(event.entity) else {
+            continue;
+        };
+        // the goal of a team that is out is walled off
+        if score.is_out(*team, &teams, setup.target_score) {
+            continue;
         }
+        score.goal(*team, &teams);
+        events_writer.send(Scored { against: *team });
+    }
+}
+
+/// The team the score alone has won the match for, which `detect_match_end`
+/// ends it with.
+pub fn score_winner(score: &Score, teams: &Teams, setup: &MatchSetup) -> Option<TeamId> {
+    // a timed match is decided by the clock, only knock-outs can end it early
+    if setup.timed.is_some() && teams.rule == WinRule::FirstTo {
+        return None;
+    }
+    score.winner(teams, setup.target_score)
+}
+
+pub fn detect_match_end(
+    score: Res<Score>,
+    teams: Res<Teams>,
+    setup: Res<MatchSetup>,
+    mut events: EventWriter<MatchFinished>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if let Some(winner) = score_winner(&score, &teams, &setup) {
+        events.send(MatchFinished {
+            final_score: score.clone(),
+            winner,
+        });
+        next_state.set(GameState::MatchOver);
     }
 }
 
+fn reset_score(mut score: ResMut<Score>) {
+    *score = Score::default();
+}
+
+fn reseed(mut rng: ResMut<GameRng>) {
+    let seed = rng.gen();
+    *rng = GameRng::new(seed);
+}
+
 pub type AllowedToRun = bool;
 // errors if you should
-pub fn countdown_guard(query: Query<&Countdown>) -> AllowedToRun {
+pub fn countdown_guard(
+    query: Query<&Countdown>,
+    state: Res<State<GameState>>,
+    time: Res<Time<Virtual>>,
+) -> AllowedToRun {
+    if *state.get() != GameState::Playing || time.is_paused() {
+        return false;
+    }
+
     for count in &query {
         if !count.timer.finished() {
             return false;
@@ -86,19 +356,163 @@ fn start_countdown(mut commands: Commands) {
     });
 }
 
-fn start_countdown_on_score(mut commands: Commands, mut events: EventReader<Scored>) {
-    for event in events.read() {
+fn start_countdown_on_score(commands: Commands, mut events: EventReader<Scored>) {
+    if events.read().next().is_some() {
         start_countdown(commands);
+    }
+}
+
+/// Carries changes made on the settings screen over to the match. Only what
+/// changed, so launch options and menu picks hold until they are edited.
+fn follow_settings(
+    settings: Res<Settings>,
+    mut setup: ResMut<MatchSetup>,
+    mut teams: ResMut<Teams>,
+    mut followed: Local<Option<Settings>>,
+) {
+    let Some(previous) = followed.replace(settings.clone()) else {
         return;
+    };
+    if previous.difficulty != settings.difficulty {
+        setup.difficulty = settings.difficulty;
+    }
+    if previous.target_score != settings.target_score {
+        setup.target_score = settings.target_score;
+    }
+    if previous.visuals.palette != settings.visuals.palette {
+        teams.recolor(settings.visuals.palette);
+    }
+}
+
+fn restart_match(
+    mut commands: Commands,
+    mut events: EventReader<RestartMatch>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if events.read().count() > 0 {
+        commands.insert_resource(Restarting);
+        next_state.set(GameState::MatchOver);
+    }
+}
+
+fn finish_restart(mut commands: Commands, mut next_state: ResMut<NextState<GameState>>) {
+    commands.remove_resource::<Restarting>();
+    next_state.set(GameState::Playing);
+}
+
+/// `score <team> <points>` sets the number on the scoreboard of a team: its
+/// points, or its goals let in when those decide the match.
+struct ScoreCommand;
+impl ConsoleCommand for ScoreCommand {
+    fn name(&self) -> &'static str {
+        "score"
+    }
+
+    fn usage(&self) -> &'static str {
+        "score <team> <points>"
+    }
+
+    fn run(&self, args: &[&str], world: &mut World) -> anyhow::Result<String> {
+        let Some((points, name)) = args.split_last().filter(|(_, name)| !name.is_empty()) else {
+            bail!("usage: {}", self.usage());
+        };
+        let points: u32 = points.parse()?;
+        // team names can have spaces, like `left ai`
+        let name = name.join(" ");
+        let teams = world.resource::<Teams>();
+        let team = teams
+            .find(&name)
+            .ok_or_else(|| anyhow!("no team called `{}`", name))?;
+        let rule = teams.rule;
+
+        let mut score = world.resource_mut::<Score>();
+        match rule {
+            WinRule::FirstTo => score.points.insert(team, points),
+            WinRule::LastStanding | WinRule::Endless => score.conceded.insert(team, points),
+        };
+        Ok(format!("{} has {}", name, points))
+    }
+}
+
+/// `reset` starts the match over.
+struct ResetCommand;
+impl ConsoleCommand for ResetCommand {
+    fn name(&self) -> &'static str {
+        "reset"
+    }
+
+    fn usage(&self) -> &'static str {
+        "reset"
+    }
+
+    fn run(&self, _: &[&str], world: &mut World) -> anyhow::Result<String> {
+        if *world.resource::<State<GameState>>().get() != GameState::Playing {
+            bail!("no match is being played");
+        }
+        world.send_event(RestartMatch);
+        Ok("restarting the match".to_string())
     }
 }
 
 pub struct GameManagerPlugin;
 impl Plugin for GameManagerPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
+        if !app.world.contains_resource::<MatchSetup>() {
+            let setup = MatchSetup::from_settings(app.world.resource::<Settings>());
+            app.insert_resource(setup);
+        }
+        let setup = app.world.resource::<MatchSetup>();
+        let palette = app.world.resource::<Settings>().visuals.palette;
+        let (seed, teams) = (setup.seed, Teams::for_mode(setup.mode, palette));
+
         app.add_event::<Scored>()
+            .add_event::<MatchFinished>()
+            .add_event::<RestartMatch>()
             .init_resource::<Score>()
-            .add_systems(Startup, start_countdown)
-            .add_systems(Update, (detect_scoring, count, start_countdown_on_score));
+            .insert_resource(teams)
+            .insert_resource(GameRng::new(seed))
+            .init_state::<GameState>()
+            .configure_sets(
+                Update,
+                GameplaySet.run_if(
+                    unpaused
+                        .and_then(simulation_running)
+                        .and_then(not(in_state(GameState::Menu))),
+                ),
+            )
+            .add_systems(
+                OnEnter(GameState::Playing),
+                (reset_score, start_countdown.run_if(countdown_serves)),
+            )
+            .add_systems(
+                OnEnter(GameState::MatchOver),
+                finish_restart.run_if(resource_exists::<Restarting>),
+            )
+            // every match after the first is seeded from the one before
+            .add_systems(OnExit(GameState::MatchOver), reseed)
+            .add_systems(
+                OnTransition {
+                    from: GameState::Playing,
+                    to: GameState::Menu,
+                },
+                reseed,
+            )
+            .add_systems(
+                Update,
+                (
+                    (detect_scoring, detect_match_end)
+                        .chain()
+                        .run_if(in_state(GameState::Playing))
+                        .in_set(GameplaySet),
+                    count,
+                    follow_settings.run_if(resource_changed::<Settings>),
+                    restart_match.run_if(in_state(GameState::Playing)),
+                    start_countdown_on_score
+                        .run_if(countdown_serves)
+                        .in_set(GameplaySet),
+                ),
+            )
+            .register_command(ScoreCommand)
+            .register_command(ResetCommand);
     }
 }
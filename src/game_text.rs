@@ -1,52 +1,69 @@
+use std::f32::consts::TAU;
+
 use bevy::{
     app::{Plugin, Startup, Update},
     ecs::{
         component::Component,
-        event::EventReader,
-        query::{self, With},
+        entity::Entity,
+        query::With,
+        schedule::OnEnter,
         system::{Commands, Query, Res},
     },
     prelude::default,
     render::color::Color,
-    text::{JustifyText, Text, TextSection, TextStyle},
-    ui::{node_bundles::TextBundle, Style, Val},
+    text::{Text, TextSection, TextStyle},
+    time::Time,
+    ui::{node_bundles::TextBundle, Style},
+};
+
+use crate::{
+    clock::{ClockPhase, MatchClock},
+    game_manager::{Countdown, GameState, MatchSetup, Score},
+    settings::Settings,
+    team::Teams,
+    utils::format_duration,
 };
 
-use crate::game_manager::{Countdown, Score};
+const CLOCK_FONT_SIZE: f32 = 40.;
 
 #[derive(Component)]
 struct ScoreText;
 
-fn spawn_score(mut commands: Commands) {
-    commands.spawn((
-        // Create a TextBundle that has a Text with a list of sections.
-        TextBundle::from_sections([
-            TextSection::new(
-                "X",
-                TextStyle {
-                    font_size: 60.0,
-                    color: Color::BLACK,
-                    ..default()
-                },
-            ),
-            TextSection::new(
+/// Lays the scoreboard out for the teams of the new match.
+fn spawn_score(
+    mut commands: Commands,
+    teams: Res<Teams>,
+    existing: Query<Entity, With<ScoreText>>,
+) {
+    for entity in &existing {
+        commands.entity(entity).despawn();
+    }
+
+    // one section per team in its color, with a separator in between
+    let mut sections = Vec::new();
+    for (id, team) in teams.iter() {
+        if id.0 > 0 {
+            sections.push(TextSection::new(
                 ":",
                 TextStyle {
                     font_size: 60.0,
                     color: Color::WHITE,
                     ..default()
                 },
-            ),
-            TextSection::new(
-                "X",
-                TextStyle {
-                    font_size: 60.0,
-                    color: Color::GREEN,
-                    ..default()
-                },
-            ),
-        ])
-        .with_style(Style {
+            ));
+        }
+        sections.push(TextSection::new(
+            "X",
+            TextStyle {
+                font_size: 60.0,
+                color: team.color,
+                ..default()
+            },
+        ));
+    }
+
+    commands.spawn((
+        TextBundle::from_sections(sections).with_style(Style {
             align_self: bevy::ui::AlignSelf::Center,
             justify_self: bevy::ui::JustifySelf::Center,
             ..default()
@@ -58,10 +75,23 @@ fn spawn_score(mut commands: Commands) {
 #[derive(Component)]
 struct CountdownText;
 
-fn udpate_score(mut text: Query<&mut Text, With<ScoreText>>, score: Res<Score>) {
-    let mut text_value = text.single_mut();
-    text_value.sections[0].value = format!("{}", score.ai);
-    text_value.sections[2].value = format!("{}", score.player);
+fn udpate_score(
+    mut text: Query<&mut Text, With<ScoreText>>,
+    score: Res<Score>,
+    teams: Res<Teams>,
+    setup: Res<MatchSetup>,
+) {
+    let Ok(mut text_value) = text.get_single_mut() else {
+        return;
+    };
+    // the teams can change before the scoreboard is laid out again
+    for (id, team) in teams.iter() {
+        if let Some(section) = text_value.sections.get_mut(2 * id.0) {
+            section.value = score.display(id, &teams, setup.target_score);
+            // the palette can be switched in the middle of a match
+            section.style.color = team.color;
+        }
+    }
 }
 
 fn spawn_countdown(mut commands: Commands) {
@@ -82,8 +112,16 @@ fn spawn_countdown(mut commands: Commands) {
     ));
 }
 
-fn update_countdown(countdown: Query<&Countdown>, mut text: Query<&mut Text, With<CountdownText>>) {
-    if let Ok(count) = countdown.get_single() {
+fn update_countdown(
+    countdown: Query<&Countdown>,
+    mut text: Query<&mut Text, With<CountdownText>>,
+    settings: Res<Settings>,
+) {
+    let count = countdown
+        .get_single()
+        .ok()
+        .filter(|_| settings.visuals.show_countdown);
+    if let Some(count) = count {
         let secs = format!("{}", count.timer.remaining().as_secs() + 1);
         text.single_mut().sections[0].value = secs;
     } else {
@@ -91,10 +129,64 @@ fn update_countdown(countdown: Query<&Countdown>, mut text: Query<&mut Text, Wit
     }
 }
 
+#[derive(Component)]
+struct ClockText;
+
+fn spawn_clock(mut commands: Commands) {
+    commands.spawn((
+        ClockText,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                color: Color::WHITE,
+                font_size: CLOCK_FONT_SIZE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            align_self: bevy::ui::AlignSelf::End,
+            justify_self: bevy::ui::JustifySelf::Center,
+            ..default()
+        }),
+    ));
+}
+
+fn update_clock(
+    clock: Option<Res<MatchClock>>,
+    mut text: Query<&mut Text, With<ClockText>>,
+    time: Res<Time>,
+) {
+    let mut text = text.single_mut();
+    let section = &mut text.sections[0];
+    let Some(clock) = clock else {
+        section.value = "".to_string();
+        return;
+    };
+
+    // the time is shown rounded up, so it reads 0:00 only once it has run out
+    let remaining = format_duration(clock.remaining.ceil());
+    section.value = match clock.phase {
+        ClockPhase::Regulation => remaining,
+        ClockPhase::Overtime => format!("OT {}", remaining),
+        ClockPhase::SuddenDeath => "SUDDEN DEATH".to_string(),
+    };
+
+    if clock.is_running_out() {
+        // pulse twice a second in red
+        let pulse = (time.elapsed_seconds() * 2. * TAU).sin().abs();
+        section.style.color = Color::RED;
+        section.style.font_size = CLOCK_FONT_SIZE * (1. + 0.2 * pulse);
+    } else {
+        section.style.color = Color::WHITE;
+        section.style.font_size = CLOCK_FONT_SIZE;
+    }
+}
+
 pub struct GameTextPlugin;
 impl Plugin for GameTextPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        app.add_systems(Startup, (spawn_score, spawn_countdown))
-            .add_systems(Update, (udpate_score, update_countdown));
+        app.add_systems(Startup, (spawn_countdown, spawn_clock))
+            .add_systems(OnEnter(GameState::Playing), spawn_score)
+            .add_systems(Update, (udpate_score, update_countdown, update_clock));
     }
 }
@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use bevy::{
+    app::{AppExit, ScheduleRunnerPlugin},
+    core::FrameCount,
+    input::InputPlugin,
+    log::LogPlugin,
+    prelude::*,
+    time::TimeUpdateStrategy,
+    utils::tracing::dispatcher,
+};
+
+use crate::{
+    game_manager::{MatchFinished, MatchSetup},
+    rng::GameRng,
+    team::Teams,
+};
+
+/// Simulated time of one frame, so a headless run plays exactly like a 60 fps one.
+const FRAME_TIME: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+/// Prints the result of the match and quits.
+pub fn report_and_exit(
+    mut events: EventReader<MatchFinished>,
+    mut exit: EventWriter<AppExit>,
+    setup: Res<MatchSetup>,
+    teams: Res<Teams>,
+    frames: Res<FrameCount>,
+    rng: Res<GameRng>,
+) {
+    for event in events.read() {
+        println!(
+            "match finished after {} frames: {}, {} wins (seed {})",
+            frames.0,
+            event.final_score.summary(&teams, setup.target_score),
+            teams.get(event.winner).name,
+            rng.seed()
+        );
+        exit.send(AppExit);
+    }
+}
+
+/// Runs the game without a window, as fast as possible. Every frame passes
+/// as a 60th of a second.
+pub struct HeadlessPlugin;
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::ZERO)),
+            InputPlugin,
+        ))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(FRAME_TIME));
+        // logging is set up once for the process, by the first app of many
+        if !dispatcher::has_been_set() {
+            app.add_plugins(LogPlugin::default());
+        }
+    }
+}
@@ -0,0 +1,277 @@
+use std::{
+    fs,
+    io::ErrorKind,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    border::Border,
+    game_manager::{GameMode, MatchFinished, MatchSetup},
+    settings::Difficulty,
+    stats::MatchStats,
+    team::Teams,
+    utils::format_duration,
+};
+
+const FILE_NAME: &str = "history.json";
+const SHOWN_MATCHES: usize = 10;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Opponent {
+    Ai(Difficulty),
+    Human,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MatchRecord {
+    /// Seconds since the unix epoch.
+    pub finished_at: u64,
+    pub player_score: u32,
+    pub ai_score: u32,
+    pub opponent: Opponent,
+    pub duration_secs: f32,
+    pub longest_rally: u32,
+    pub max_ball_speed: f32,
+}
+
+impl MatchRecord {
+    fn won(&self) -> bool {
+        self.player_score > self.ai_score
+    }
+}
+
+/// Every completed match, oldest first. Stored as JSON in the platform data directory.
+#[derive(Resource, Default)]
+pub struct MatchHistory {
+    pub matches: Vec<MatchRecord>,
+}
+
+#[derive(Component)]
+struct HistoryScreen;
+
+#[derive(Component)]
+struct HistoryText;
+
+fn history_path() -> PathBuf {
+    dirs::data_dir()
+        .map(|dir| dir.join("bevy_pong"))
+        .unwrap_or_default()
+        .join(FILE_NAME)
+}
+
+fn load_history() -> MatchHistory {
+    let path = history_path();
+    let parsed = fs::read_to_string(&path)
+        .map_err(anyhow::Error::from)
+        .and_then(|text| serde_json::from_str(&text).map_err(anyhow::Error::from));
+
+    match parsed {
+        Ok(matches) => MatchHistory { matches },
+        Err(err) => {
+            let missing = err
+                .downcast_ref::<std::io::Error>()
+                .is_some_and(|err| err.kind() == ErrorKind::NotFound);
+            if !missing {
+                warn!(
+                    "starting a new history, could not load {}: {}",
+                    path.display(),
+                    err
+                );
+            }
+            MatchHistory::default()
+        }
+    }
+}
+
+fn save_history(history: &MatchHistory) -> anyhow::Result<()> {
+    let path = history_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(&history.matches)?)?;
+    Ok(())
+}
+
+fn record_match(
+    mut events: EventReader<MatchFinished>,
+    stats: Res<MatchStats>,
+    mut history: ResMut<MatchHistory>,
+    setup: Res<MatchSetup>,
+    teams: Res<Teams>,
+) {
+    let opponent = match setup.mode {
+        GameMode::VsAi => Opponent::Ai(setup.difficulty),
+        GameMode::LocalTwoPlayer => Opponent::Human,
+        // nobody to keep a personal history for
+        GameMode::AiVsAi => return,
+        // the history only knows two sided scores
+        GameMode::Quadrapong | GameMode::Practice => return,
+    };
+    let (Some(player), Some(ai)) = (teams.owner(Border::Right), teams.owner(Border::Left)) else {
+        return;
+    };
+
+    for event in events.read() {
+        let finished_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_secs())
+            .unwrap_or_default();
+
+        history.matches.push(MatchRecord {
+            finished_at,
+            player_score: event.final_score.points(player),
+            ai_score: event.final_score.points(ai),
+            opponent,
+            duration_secs: stats.duration_secs,
+            longest_rally: stats.longest_rally,
+            max_ball_speed: stats.peak_speed,
+        });
+
+        if let Err(err) = save_history(&history) {
+            warn!("could not save match history: {}", err);
+        }
+    }
+}
+
+/// Formats unix seconds as a `YYYY-MM-DD` date (UTC).
+fn format_date(secs: u64) -> String {
+    // days to civil date, from http://howardhinnant.github.io/date_algorithms.html
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+fn describe_opponent(opponent: &Opponent) -> String {
+    match opponent {
+        Opponent::Ai(difficulty) => format!("{:?} AI", difficulty),
+        Opponent::Human => "local player".to_string(),
+    }
+}
+
+fn history_report(history: &MatchHistory) -> String {
+    let matches = &history.matches;
+    if matches.is_empty() {
+        return "Match history\n\nNo matches played yet".to_string();
+    }
+
+    let wins = matches.iter().filter(|record| record.won()).count();
+    let longest_rally = matches.iter().map(|r| r.longest_rally).max().unwrap_or(0);
+    let top_speed = matches.iter().map(|r| r.max_ball_speed).fold(0., f32::max);
+    let quickest_win = matches
+        .iter()
+        .filter(|record| record.won())
+        .map(|record| record.duration_secs)
+        .min_by(f32::total_cmp);
+
+    let mut report = format!(
+        "Match history\n\nPersonal bests\nwins: {} / {}\nlongest rally: {}\ntop ball speed: {:.1}\nquickest win: {}\n\nRecent matches\n",
+        wins,
+        matches.len(),
+        longest_rally,
+        top_speed,
+        quickest_win.map_or("-".to_string(), format_duration),
+    );
+
+    for record in matches.iter().rev().take(SHOWN_MATCHES) {
+        report += &format!(
+            "{}  {} - {}  {}  vs {}  {}  rally {}  speed {:.1}\n",
+            format_date(record.finished_at),
+            record.player_score,
+            record.ai_score,
+            if record.won() { "won" } else { "lost" },
+            describe_opponent(&record.opponent),
+            format_duration(record.duration_secs),
+            record.longest_rally,
+            record.max_ball_speed,
+        );
+    }
+
+    report
+}
+
+fn spawn_history_screen(mut commands: Commands) {
+    commands
+        .spawn((
+            HistoryScreen,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.85).into(),
+                visibility: Visibility::Hidden,
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                HistoryText,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 24.,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+            ));
+        });
+}
+
+fn toggle_history_screen(
+    input: Res<ButtonInput<KeyCode>>,
+    mut screen: Query<&mut Visibility, With<HistoryScreen>>,
+) {
+    if !input.just_pressed(KeyCode::KeyH) {
+        return;
+    }
+
+    if let Ok(mut visibility) = screen.get_single_mut() {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Visible,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+fn update_history_text(history: Res<MatchHistory>, mut text: Query<&mut Text, With<HistoryText>>) {
+    if !history.is_changed() {
+        return;
+    }
+
+    if let Ok(mut text) = text.get_single_mut() {
+        text.sections[0].value = history_report(&history);
+    }
+}
+
+pub struct HistoryPlugin;
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(load_history())
+            .add_systems(Startup, spawn_history_screen)
+            .add_systems(
+                Update,
+                (record_match, toggle_history_screen, update_history_text),
+            );
+    }
+}
@@ -0,0 +1,63 @@
+use std::path::Path;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{read_ron, Position, Shape};
+
+/// A wall placed inside the arena by a level. The ball bounces off it like
+/// off a paddle.
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct Obstacle {
+    pub position: Vec2,
+    pub size: Vec2,
+}
+
+/// Layout of the arena, loaded from a RON file such as `assets/levels/pillars.ron`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct Level {
+    pub obstacles: Vec<Obstacle>,
+}
+
+impl Level {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        read_ron(path)
+    }
+}
+
+fn spawn(mut commands: Commands, level: Res<CurrentLevel>) {
+    for obstacle in &level.0.obstacles {
+        commands.spawn((
+            obstacle.clone(),
+            Position(obstacle.position),
+            Shape::Rectangle {
+                width: obstacle.size.x,
+                height: obstacle.size.y,
+            },
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::GRAY,
+                    custom_size: Some(obstacle.size),
+                    ..default()
+                },
+                transform: Transform::from_translation(obstacle.position.extend(0.)),
+                ..default()
+            },
+        ));
+    }
+}
+
+#[derive(Resource)]
+struct CurrentLevel(Level);
+
+pub struct LevelPlugin {
+    pub level: Level,
+}
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(CurrentLevel(self.level.clone()))
+            .add_systems(Startup, spawn);
+    }
+}
@@ -0,0 +1,65 @@
+//! Pong, built with Bevy. The game is a set of plugins shared by the game
+//! binary, the tournament runner and anything else driving the simulation.
+
+pub mod arena;
+pub mod ball;
+pub mod border;
+pub mod bot;
+pub mod cli;
+pub mod clock;
+pub mod console;
+pub mod court;
+pub mod debug;
+pub mod env;
+pub mod external_bot;
+pub mod game_manager;
+pub mod game_text;
+pub mod headless;
+pub mod history;
+pub mod level;
+pub mod main_menu;
+pub mod paddle;
+pub mod pause;
+pub mod replay;
+pub mod rng;
+pub mod serve;
+pub mod settings;
+pub mod settings_screen;
+pub mod spritesheet_animation;
+pub mod stats;
+pub mod team;
+pub mod time_scale;
+pub mod utils;
+pub mod widgets;
+
+use bevy::prelude::*;
+
+use ball::BallPlugin;
+use border::BordersPlugin;
+use bot::BotPlugin;
+use clock::ClockPlugin;
+use game_manager::{GameManagerPlugin, MatchSetup};
+use level::{Level, LevelPlugin};
+use paddle::PaddlesPlugin;
+use serve::ServePlugin;
+use settings::SettingsPlugin;
+use stats::StatsPlugin;
+use time_scale::TimeScalePlugin;
+
+/// Adds the plugins that play the game, with or without a window, starting
+/// with a match set up as `setup`.
+pub fn add_game_plugins(app: &mut App, settings: SettingsPlugin, setup: MatchSetup, level: Level) {
+    app.insert_resource(setup).add_plugins((
+        settings,
+        BallPlugin,
+        PaddlesPlugin,
+        BordersPlugin,
+        GameManagerPlugin,
+        StatsPlugin,
+        ServePlugin,
+        ClockPlugin,
+        TimeScalePlugin,
+        LevelPlugin { level },
+        BotPlugin,
+    ));
+}
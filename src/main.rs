@@ -1,34 +1,75 @@
-mod ball;
-mod border;
-mod game_manager;
-mod game_text;
-mod paddle;
-mod spritesheet_animation;
+use std::{process::ExitCode, time::Duration};
 
-mod utils;
-use ball::BallPlugin;
 use bevy::prelude::*;
-use border::BordersPlugin;
-use game_manager::GameManagerPlugin;
-use game_text::GameTextPlugin;
-use paddle::PaddlesPlugin;
-use spritesheet_animation::SpritesheetAnimationPlugin;
+use bevy_pong::{
+    add_game_plugins,
+    arena::ArenaPlugin,
+    bot::BotRegistry,
+    cli::{Cli, Mode},
+    console::ConsolePlugin,
+    court::CourtPlugin,
+    debug::DebugPlugin,
+    external_bot::register_external_bots,
+    game_manager::MatchSetup,
+    game_text::GameTextPlugin,
+    headless::{report_and_exit, HeadlessPlugin},
+    history::HistoryPlugin,
+    main_menu::MainMenuPlugin,
+    pause::PausePlugin,
+    replay::ReplayPlugin,
+    settings::SettingsPlugin,
+    settings_screen::SettingsScreenPlugin,
+    spritesheet_animation::SpritesheetAnimationPlugin,
+};
+use clap::Parser;
 
-fn spawn_camera(mut commands: Commands) {
-    commands.spawn_empty().insert(Camera2dBundle::default());
-}
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let (level, replay) = match cli.validate() {
+        Ok(loaded) => loaded,
+        Err(err) => {
+            eprintln!("error: {:#}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    let settings = SettingsPlugin::load();
+    let open_menu = cli.opens_menu();
 
-fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins)
+    let mut app = App::new();
+    if cli.mode == Some(Mode::Headless) {
+        app.add_plugins(HeadlessPlugin)
+            .add_systems(Update, report_and_exit);
+    } else {
+        app.add_plugins(DefaultPlugins.set(WindowPlugin {
+            primary_window: Some(cli.window(settings.window())),
+            ..default()
+        }))
         .add_plugins((
+            ArenaPlugin,
+            ConsolePlugin,
+            CourtPlugin,
+            DebugPlugin,
             SpritesheetAnimationPlugin,
-            BallPlugin,
-            PaddlesPlugin,
-            BordersPlugin,
-            GameManagerPlugin,
             GameTextPlugin,
-        ))
-        .add_systems(Startup, spawn_camera)
-        .run();
+            HistoryPlugin,
+            PausePlugin,
+            MainMenuPlugin { open: open_menu },
+            SettingsScreenPlugin,
+            ReplayPlugin { replay },
+        ));
+    }
+
+    let setup = cli.match_setup(settings.settings());
+    add_game_plugins(&mut app, settings, setup, level);
+
+    let timeout = Duration::from_millis(cli.bot_timeout_ms);
+    register_external_bots(&mut app, &cli.external_bot, timeout);
+    let bots = app.world.resource::<BotRegistry>();
+    if let Err(err) = bots.check(app.world.resource::<MatchSetup>()) {
+        eprintln!("error: {:#}", err);
+        return ExitCode::FAILURE;
+    }
+    app.run();
+
+    ExitCode::SUCCESS
 }
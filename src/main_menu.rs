@@ -0,0 +1,262 @@
+use std::path::PathBuf;
+
+use bevy::{app::AppExit, prelude::*};
+
+use crate::{
+    game_manager::{GameMode, GameState, MatchSetup},
+    replay::{saved_replays, Playback, Replay},
+    settings::{Difficulty, Settings},
+    settings_screen::SettingsScreen,
+    team::Teams,
+    widgets::{
+        highlight_focus, navigate_menu, show_hint, spawn_buttons, spawn_menu, MenuFocus, MenuHint,
+        MenuInput,
+    },
+};
+
+/// The replays page lists this many of the latest replays.
+const SHOWN_REPLAYS: usize = 10;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Page {
+    Main,
+    Difficulty,
+    Replays,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum MenuEntry {
+    PlayVsAi,
+    LocalTwoPlayer,
+    Practice,
+    Replays,
+    Settings,
+    Quit,
+    Difficulty(Difficulty),
+    Replay(PathBuf),
+    Back,
+}
+
+/// The page on screen and what its buttons do, from top to bottom.
+#[derive(Resource)]
+struct MainMenu {
+    page: Page,
+    entries: Vec<MenuEntry>,
+}
+
+/// The match options at launch, which the menu picks the mode and difficulty on top of.
+#[derive(Resource)]
+struct LaunchSetup(MatchSetup);
+
+#[derive(Component)]
+struct MainMenuScreen;
+
+fn label(entry: &MenuEntry) -> String {
+    match entry {
+        MenuEntry::PlayVsAi => "Play vs AI".to_string(),
+        MenuEntry::LocalTwoPlayer => "Local 2P".to_string(),
+        MenuEntry::Practice => "Practice".to_string(),
+        MenuEntry::Replays => "Replays".to_string(),
+        MenuEntry::Settings => "Settings".to_string(),
+        MenuEntry::Quit => "Quit".to_string(),
+        MenuEntry::Difficulty(difficulty) => format!("{:?}", difficulty),
+        MenuEntry::Replay(path) => path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        MenuEntry::Back => "Back".to_string(),
+    }
+}
+
+fn page_entries(page: Page) -> Vec<MenuEntry> {
+    match page {
+        Page::Main => vec![
+            MenuEntry::PlayVsAi,
+            MenuEntry::LocalTwoPlayer,
+            MenuEntry::Practice,
+            MenuEntry::Replays,
+            MenuEntry::Settings,
+            MenuEntry::Quit,
+        ],
+        Page::Difficulty => [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard]
+            .into_iter()
+            .map(MenuEntry::Difficulty)
+            .chain([MenuEntry::Back])
+            .collect(),
+        Page::Replays => saved_replays()
+            .into_iter()
+            .take(SHOWN_REPLAYS)
+            .map(MenuEntry::Replay)
+            .chain([MenuEntry::Back])
+            .collect(),
+    }
+}
+
+/// Replaces the menu on screen with `page`.
+fn show_page(
+    commands: &mut Commands,
+    screens: &Query<Entity, With<MainMenuScreen>>,
+    page: Page,
+    setup: &MatchSetup,
+) {
+    for entity in screens {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let entries = page_entries(page);
+    let labels: Vec<String> = entries.iter().map(label).collect();
+    let title = match page {
+        Page::Main => "PONG",
+        Page::Difficulty => "DIFFICULTY",
+        Page::Replays => "REPLAYS",
+    };
+    spawn_menu(commands, title, Color::rgb(0.05, 0.05, 0.05))
+        .insert(MainMenuScreen)
+        .with_children(|parent| {
+            spawn_buttons(parent, labels.iter().map(String::as_str), 32.);
+        });
+
+    // start on the difficulty that was played last
+    if page == Page::Difficulty {
+        let current = MenuEntry::Difficulty(setup.difficulty);
+        if let Some(index) = entries.iter().position(|entry| *entry == current) {
+            commands.insert_resource(MenuFocus(index));
+        }
+    }
+    commands.insert_resource(MainMenu { page, entries });
+}
+
+fn remember_launch_setup(mut commands: Commands, setup: Res<MatchSetup>) {
+    commands.insert_resource(LaunchSetup(setup.clone()));
+}
+
+fn open_menu(
+    mut commands: Commands,
+    screens: Query<Entity, With<MainMenuScreen>>,
+    setup: Res<MatchSetup>,
+) {
+    show_page(&mut commands, &screens, Page::Main, &setup);
+}
+
+/// Back from the settings screen, on the entry that opened it.
+fn focus_settings(mut focus: ResMut<MenuFocus>) {
+    focus.0 = page_entries(Page::Main)
+        .iter()
+        .position(|entry| *entry == MenuEntry::Settings)
+        .unwrap_or_default();
+}
+
+fn close_menu(mut commands: Commands, screens: Query<Entity, With<MainMenuScreen>>) {
+    for entity in &screens {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn apply_choice(
+    In(input): In<Option<MenuInput>>,
+    mut commands: Commands,
+    screens: Query<Entity, With<MainMenuScreen>>,
+    menu: Res<MainMenu>,
+    launch: Res<LaunchSetup>,
+    mut setup: ResMut<MatchSetup>,
+    mut teams: ResMut<Teams>,
+    settings: Res<Settings>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut next_settings_state: ResMut<NextState<SettingsScreen>>,
+    mut exit: EventWriter<AppExit>,
+    mut hint: Query<&mut Text, With<MenuHint>>,
+) {
+    let entry = match input {
+        None | Some(MenuInput::Adjust(..)) => return,
+        Some(MenuInput::Back) if menu.page == Page::Main => return,
+        Some(MenuInput::Back) => MenuEntry::Back,
+        Some(MenuInput::Pick(index)) => match menu.entries.get(index) {
+            Some(entry) => entry.clone(),
+            None => return,
+        },
+    };
+
+    let mode = match entry {
+        MenuEntry::PlayVsAi => {
+            show_page(&mut commands, &screens, Page::Difficulty, &setup);
+            return;
+        }
+        MenuEntry::Difficulty(difficulty) => {
+            setup.difficulty = difficulty;
+            GameMode::VsAi
+        }
+        MenuEntry::LocalTwoPlayer => GameMode::LocalTwoPlayer,
+        MenuEntry::Practice => GameMode::Practice,
+        MenuEntry::Replays => {
+            if saved_replays().is_empty() {
+                show_hint(&mut hint, "no replays saved yet".to_string());
+            } else {
+                show_page(&mut commands, &screens, Page::Replays, &setup);
+            }
+            return;
+        }
+        MenuEntry::Replay(path) => match Replay::load(&path) {
+            Ok(replay) => {
+                commands.insert_resource(Playback::new(&replay));
+                GameMode::VsAi
+            }
+            Err(err) => {
+                show_hint(&mut hint, format!("could not load replay: {:#}", err));
+                return;
+            }
+        },
+        MenuEntry::Settings => {
+            next_settings_state.set(SettingsScreen::Open);
+            return;
+        }
+        MenuEntry::Quit => {
+            exit.send(AppExit);
+            return;
+        }
+        MenuEntry::Back => {
+            show_page(&mut commands, &screens, Page::Main, &setup);
+            return;
+        }
+    };
+
+    // hand the picked options to the match setup and start the match
+    *setup = MatchSetup {
+        difficulty: setup.difficulty,
+        target_score: setup.target_score,
+        ..launch.0.with_mode(mode)
+    };
+    *teams = Teams::for_mode(mode, settings.visuals.palette);
+    next_state.set(GameState::Playing);
+}
+
+/// The screen the game opens on, and returns to from the pause menu. Picks
+/// the mode and difficulty of the next match, or a replay to watch.
+pub struct MainMenuPlugin {
+    /// Open on the menu rather than go straight into a match.
+    pub open: bool,
+}
+
+impl Plugin for MainMenuPlugin {
+    fn build(&self, app: &mut App) {
+        if self.open {
+            app.insert_state(GameState::Menu);
+        }
+        app.add_systems(Startup, remember_launch_setup)
+            .add_systems(OnEnter(GameState::Menu), open_menu)
+            .add_systems(OnExit(GameState::Menu), close_menu)
+            // the settings screen takes the place of the menu while it is open
+            .add_systems(OnEnter(SettingsScreen::Open), close_menu)
+            .add_systems(
+                OnExit(SettingsScreen::Open),
+                (open_menu, focus_settings)
+                    .chain()
+                    .run_if(in_state(GameState::Menu)),
+            )
+            .add_systems(
+                Update,
+                (navigate_menu.pipe(apply_choice), highlight_focus)
+                    .chain()
+                    .run_if(in_state(GameState::Menu).and_then(in_state(SettingsScreen::Closed))),
+            );
+    }
+}
@@ -1,150 +1,564 @@
+use std::f32::consts::FRAC_PI_2;
+
 use crate::{
     ball::Ball,
-    utils::{project_positions, Position, Shape, Velocity},
+    border::Border,
+    bot::{BotRegistry, MoveCommand, Observation, PaddleController, PaddleState},
+    console::{ConsoleCommand, RegisterCommand},
+    game_manager::{GameMode, GameState, GameplaySet, MatchSetup, Score, Scored},
+    settings::{Difficulty, Settings},
+    team::{TeamId, Teams},
+    time_scale::TimeScale,
+    utils::{
+        asset_path, load_ron_or_default, project_positions, Collision, Position, Shape, Velocity,
+        ARENA_SIZE,
+    },
 };
-use anyhow::Result;
-use bevy::{prelude::*, sprite::MaterialMesh2dBundle, transform::commands};
+use bevy::{ecs::system::SystemParam, prelude::*};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
 
 const WIDTH: f32 = 30.;
 const HEIGHT: f32 = 100.;
 const SPEED: f32 = 5.;
+const TUNING_PATH: &str = "config/paddles.ron";
 
 #[derive(Component)]
 pub struct Paddle;
 
+/// The right side paddle. Who moves it is decided by its `Controller`.
 #[derive(Component)]
 pub struct Player;
 
+/// The left side paddle. Who moves it is decided by its `Controller`.
 #[derive(Component)]
 pub struct Ai;
 
+#[derive(Component, ValueEnum, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Controller {
+    /// Moved with the primary key bindings.
+    #[value(name = "p1")]
+    FirstPlayer,
+    /// Moved with the second player's key bindings.
+    #[value(name = "p2")]
+    SecondPlayer,
+    Ai,
+}
+
+impl MatchSetup {
+    /// Who moves the paddle guarding `side`.
+    fn controller(&self, side: Border) -> Controller {
+        let (right, left) = match self.mode {
+            // there is no left paddle in practice
+            GameMode::VsAi | GameMode::Practice => (Controller::FirstPlayer, Controller::Ai),
+            GameMode::LocalTwoPlayer => (Controller::FirstPlayer, Controller::SecondPlayer),
+            GameMode::AiVsAi => (Controller::Ai, Controller::Ai),
+            GameMode::Quadrapong => {
+                let [right, left, top, bottom] = self.seats;
+                return match side {
+                    Border::Right => right,
+                    Border::Left => left,
+                    Border::Top => top,
+                    Border::Bottom => bottom,
+                };
+            }
+        };
+        if side == Border::Left {
+            left
+        } else {
+            right
+        }
+    }
+
+    /// Name of the bot moving the paddle guarding `side` when the AI has it.
+    fn bot(&self, side: Border) -> &str {
+        let index = match side {
+            Border::Right => 0,
+            Border::Left => 1,
+            Border::Top => 2,
+            Border::Bottom => 3,
+        };
+        &self.bots[index]
+    }
+}
+
+/// The controller moving an AI paddle.
+#[derive(Component)]
+struct Bot(Box<dyn PaddleController>);
+
+/// The border behind the paddle, which it keeps the ball out of.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Goal(pub Border);
+
+/// Unit direction the paddle slides along: up for the side paddles, right
+/// for the top and bottom ones.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Rail(pub Vec2);
+
+impl Rail {
+    fn along(side: Border) -> Self {
+        match side {
+            Border::Left | Border::Right => Rail(Vec2::Y),
+            Border::Top | Border::Bottom => Rail(Vec2::X),
+        }
+    }
+
+    fn is_horizontal(&self) -> bool {
+        self.0.y == 0.
+    }
+
+    /// Whether `side` of the paddle is one of its faces rather than one of its ends.
+    pub fn is_face(&self, side: Collision) -> bool {
+        match side {
+            Collision::Left | Collision::Right => !self.is_horizontal(),
+            Collision::Top | Collision::Bottom => self.is_horizontal(),
+        }
+    }
+}
+
+/// Velocity along its `Rail` the paddle's controller asks for. `apply_motion`
+/// turns it into the actual `Velocity`.
+#[derive(Component, Default)]
+pub struct MoveIntent(pub f32);
+
+/// Momentum for a paddle: without it the paddle moves at the requested
+/// speed instantly. All values are in units per frame.
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct PaddleMotion {
+    /// Speed gained per frame while moving.
+    pub acceleration: f32,
+    pub max_speed: f32,
+    /// Speed lost per frame when no movement is requested.
+    pub friction: f32,
+}
+
+/// Per paddle motion settings, loaded from `assets/config/paddles.ron`.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct PaddleTuning {
+    pub right: Option<PaddleMotion>,
+    pub left: Option<PaddleMotion>,
+    /// Only used in Quadrapong.
+    pub top: Option<PaddleMotion>,
+    pub bottom: Option<PaddleMotion>,
+}
+
+impl PaddleTuning {
+    fn motion(&self, side: Border) -> Option<PaddleMotion> {
+        match side {
+            Border::Right => self.right,
+            Border::Left => self.left,
+            Border::Top => self.top,
+            Border::Bottom => self.bottom,
+        }
+    }
+}
+
 #[derive(Bundle)]
 struct PaddleBundle {
     paddle: Paddle,
     position: Position,
     velocity: Velocity,
     shape: Shape,
+    goal: Goal,
+    rail: Rail,
+    controller: Controller,
+    intent: MoveIntent,
+}
+
+impl PaddleBundle {
+    fn new(position: Vec2, side: Border, controller: Controller) -> Self {
+        let rail = Rail::along(side);
+        let (width, height) = if rail.is_horizontal() {
+            (HEIGHT, WIDTH)
+        } else {
+            (WIDTH, HEIGHT)
+        };
+        PaddleBundle {
+            paddle: Paddle,
+            shape: Shape::Rectangle { width, height },
+            position: Position(position),
+            velocity: Velocity(Vec2::new(0., 0.)),
+            goal: Goal(side),
+            rail,
+            controller,
+            intent: MoveIntent::default(),
+        }
+    }
 }
 
+/// Spawns a paddle in front of every team's goal, or brings back the ones
+/// knocked out of the last match.
 fn spawn(
     mut commmands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    window: Query<&Window>,
-    asset_server: Res<AssetServer>,
+    setup: Res<MatchSetup>,
+    teams: Res<Teams>,
+    tuning: Res<PaddleTuning>,
+    bots: Res<BotRegistry>,
+    existing: Query<&Goal, With<Paddle>>,
 ) {
-    let window_width = window.get_single().unwrap().resolution.width();
+    let size = ARENA_SIZE;
     let padding = 50.;
-    let right_paddle_x = window_width / 2. - padding;
-    let left_paddle_x = -window_width / 2. + padding;
 
-    let shape = Mesh::from(Rectangle::new(WIDTH, HEIGHT));
-    let player_color = ColorMaterial::from(Color::GREEN);
-    let ai_color = ColorMaterial::from(Color::BLACK);
+    for (team, side) in teams
+        .iter()
+        .flat_map(|(id, team)| team.goals.iter().map(move |side| (id, *side)))
+    {
+        if existing.iter().any(|goal| goal.0 == side) {
+            continue;
+        }
 
-    let mesh_handle = meshes.add(shape);
-    let player_color_handle = materials.add(player_color);
-    let ai_color_handle = materials.add(ai_color);
+        let position = match side {
+            Border::Right => Vec2::new(size.x / 2. - padding, -25.),
+            Border::Left => Vec2::new(-size.x / 2. + padding, -25.),
+            Border::Top => Vec2::new(0., size.y / 2. - padding),
+            Border::Bottom => Vec2::new(0., -size.y / 2. + padding),
+        };
+        let controller = setup.controller(side);
+        let mut paddle = commmands.spawn((team, PaddleBundle::new(position, side, controller)));
+        if controller == Controller::Ai {
+            // the names were checked at launch
+            if let Some(bot) = bots.create(setup.bot(side)) {
+                paddle.insert(Bot(bot));
+            }
+        }
+        match side {
+            Border::Right => {
+                paddle.insert(Player);
+            }
+            Border::Left => {
+                paddle.insert(Ai);
+            }
+            Border::Top | Border::Bottom => {}
+        }
+        if let Some(motion) = tuning.motion(side) {
+            paddle.insert(motion);
+        }
+    }
+}
 
-    commmands.spawn((
-        Player,
-        PaddleBundle {
-            paddle: Paddle,
-            shape: Shape::Rectangle {
-                width: WIDTH,
-                height: HEIGHT,
-            },
-            position: Position(Vec2 {
-                x: right_paddle_x,
-                y: -25.,
-            }),
-            velocity: Velocity(Vec2::new(0., 0.)),
-        },
-        SpriteBundle {
-            texture: asset_server.load("paddle.png"),
-            sprite: Sprite {
-                custom_size: Some(Vec2::new(WIDTH, HEIGHT)),
-                ..default()
-            },
-            ..default()
-        },
-    ));
+/// Clears the court for the next match, which may be played in another mode.
+fn despawn(mut commands: Commands, paddles: Query<Entity, With<Paddle>>) {
+    for entity in &paddles {
+        commands.entity(entity).despawn_recursive();
+    }
+}
 
-    commmands.spawn((
-        Ai,
-        PaddleBundle {
-            paddle: Paddle,
-            shape: Shape::Rectangle {
-                width: WIDTH,
-                height: HEIGHT,
-            },
-            position: Position(Vec2 {
-                x: left_paddle_x,
-                y: -25.,
-            }),
-            velocity: Velocity(Vec2::new(0., 0.)),
-        },
-        SpriteBundle {
+/// Takes the paddles of a team knocked out of the match off the court.
+fn retire_knocked_out(
+    mut commands: Commands,
+    mut events: EventReader<Scored>,
+    score: Res<Score>,
+    teams: Res<Teams>,
+    setup: Res<MatchSetup>,
+    paddles: Query<(Entity, &TeamId), With<Paddle>>,
+) {
+    for Scored { against } in events.read() {
+        if !score.is_out(*against, &teams, setup.target_score) {
+            continue;
+        }
+        for (entity, team) in &paddles {
+            if team == against {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+}
+
+fn attach_sprites(
+    mut commands: Commands,
+    paddles: Query<(Entity, &Rail), Added<Paddle>>,
+    asset_server: Res<AssetServer>,
+) {
+    for (entity, rail) in &paddles {
+        // the texture is upright, lay it down for the top and bottom paddles
+        let rotation = if rail.is_horizontal() { FRAC_PI_2 } else { 0. };
+        commands.entity(entity).insert(SpriteBundle {
             texture: asset_server.load("paddle.png"),
             sprite: Sprite {
                 custom_size: Some(Vec2::new(WIDTH, HEIGHT)),
                 ..default()
             },
+            transform: Transform::from_rotation(Quat::from_rotation_z(rotation)),
             ..default()
-        },
-    ));
+        });
+    }
+}
+
+impl Difficulty {
+    fn ai_speed(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 3.,
+            Difficulty::Normal => SPEED,
+            Difficulty::Hard => 7.,
+        }
+    }
 }
 
 fn handle_input(
     input: Res<ButtonInput<KeyCode>>,
-    mut paddle: Query<&mut Velocity, (With<Paddle>, With<Player>)>,
+    settings: Res<Settings>,
+    mut paddles: Query<(&mut MoveIntent, &Controller, &Rail), With<Paddle>>,
 ) {
-    if let Ok(mut velocity) = paddle.get_single_mut() {
-        if input.pressed(KeyCode::ArrowDown) {
-            velocity.0.y = -SPEED;
-        } else if input.pressed(KeyCode::ArrowUp) {
-            velocity.0.y = SPEED;
+    let keys = &settings.key_bindings;
+    for (mut intent, controller, rail) in &mut paddles {
+        let (forward, back) = match (controller, rail.is_horizontal()) {
+            (Controller::FirstPlayer, false) => (keys.up, keys.down),
+            (Controller::FirstPlayer, true) => (keys.right, keys.left),
+            (Controller::SecondPlayer, false) => (keys.second_up, keys.second_down),
+            (Controller::SecondPlayer, true) => (keys.second_right, keys.second_left),
+            (Controller::Ai, _) => continue,
+        };
+
+        if input.pressed(back) {
+            intent.0 = -SPEED;
+        } else if input.pressed(forward) {
+            intent.0 = SPEED;
         } else {
-            velocity.0.y = 0.;
+            intent.0 = 0.;
         }
     }
 }
 
-fn ai_paddle(
-    mut paddle: Query<(&mut Velocity, &Position), (With<Paddle>, With<Ai>)>,
-    ball: Query<&Position, With<Ball>>,
+/// What bots are told about the match, read from the world.
+#[derive(SystemParam)]
+pub struct Observer<'w, 's> {
+    paddles: Query<
+        'w,
+        's,
+        (
+            &'static Position,
+            &'static Velocity,
+            &'static Rail,
+            &'static Goal,
+            &'static TeamId,
+        ),
+        With<Paddle>,
+    >,
+    ball: Query<'w, 's, (&'static Position, &'static Velocity), With<Ball>>,
+    score: Res<'w, Score>,
+    teams: Res<'w, Teams>,
+}
+
+impl Observer<'_, '_> {
+    /// What the bot moving `paddle` sees, while there is a ball on the court.
+    pub fn observe(&self, paddle: Entity) -> Option<Observation> {
+        let (ball_position, ball_velocity) = self.ball.get_single().ok()?;
+        let paddle = self.paddles.get(paddle).ok()?;
+        let state =
+            |(position, velocity, rail, goal, _): (&Position, &Velocity, &Rail, &Goal, &TeamId)| {
+                PaddleState {
+                    position: position.0,
+                    velocity: velocity.0,
+                    rail: rail.0,
+                    goal: goal.0,
+                }
+            };
+
+        let team = *paddle.4;
+        Some(Observation {
+            paddle: state(paddle),
+            opponents: self
+                .paddles
+                .iter()
+                .filter(|other| *other.4 != team)
+                .map(state)
+                .collect(),
+            ball_position: ball_position.0,
+            ball_velocity: ball_velocity.0,
+            arena: ARENA_SIZE,
+            score: self.score.counted(team, &self.teams),
+            opponent_score: self
+                .teams
+                .iter()
+                .filter(|(id, _)| *id != team)
+                .map(|(id, _)| self.score.counted(id, &self.teams))
+                .max()
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// Asks the bots how to move their paddles.
+fn drive_bots(
+    mut bots: Query<(Entity, &mut Bot, &mut MoveIntent)>,
+    observer: Observer,
+    setup: Res<MatchSetup>,
 ) {
-    let _ = || -> Result<()> {
-        let (mut ai_velocity, ai_position) = paddle.get_single_mut()?;
-        let ball_position = ball.get_single()?;
-
-        let diff = ai_position.0 - ball_position.0;
-        let y_diff = diff.y;
-        if y_diff > 0. {
-            ai_velocity.0.y = -SPEED;
-        } else if y_diff < 0. {
-            ai_velocity.0.y = SPEED;
+    let speed = setup.difficulty.ai_speed();
+    for (entity, mut bot, mut intent) in &mut bots {
+        let Some(observation) = observer.observe(entity) else {
+            continue;
+        };
+        let MoveCommand(command) = bot.0.act(&observation);
+        // nothing a bot answers can send the paddle flying
+        intent.0 = if command.is_finite() {
+            command.clamp(-1., 1.) * speed
         } else {
-            ai_velocity.0.y = 0.;
-        }
+            0.
+        };
+    }
+}
+
+fn apply_motion(
+    mut paddles: Query<
+        (&mut Velocity, &MoveIntent, &Rail, Option<&PaddleMotion>),
+        (With<Paddle>, Without<God>),
+    >,
+    time_scale: Res<TimeScale>,
+) {
+    for (mut velocity, intent, rail, motion) in &mut paddles {
+        let Some(motion) = motion else {
+            velocity.0 = rail.0 * intent.0;
+            continue;
+        };
 
-        Ok(())
-    }();
+        let current = velocity.0.dot(rail.0);
+        let target = intent.0.clamp(-motion.max_speed, motion.max_speed);
+        let rate = if intent.0 == 0. {
+            motion.friction
+        } else {
+            motion.acceleration
+        };
+        let rate = rate * time_scale.factor();
+        velocity.0 = rail.0 * (current + (target - current).clamp(-rate, rate));
+    }
 }
 
-fn move_paddles(mut paddles: Query<(&mut Position, &Velocity), With<Paddle>>) {
+pub fn move_paddles(
+    mut paddles: Query<(&mut Position, &Velocity), (With<Paddle>, Without<God>)>,
+    time_scale: Res<TimeScale>,
+) {
     for (mut position, velocity) in &mut paddles {
-        position.0 += velocity.0;
+        position.0 += velocity.0 * time_scale.factor();
+    }
+}
+
+/// A paddle stretched over its whole side of the court, holding on to the
+/// shape it had before.
+#[derive(Component)]
+pub struct God(Shape);
+
+/// `ai difficulty <difficulty>` changes how fast the AI paddles move.
+struct AiCommand;
+impl ConsoleCommand for AiCommand {
+    fn name(&self) -> &'static str {
+        "ai"
+    }
+
+    fn usage(&self) -> &'static str {
+        "ai difficulty easy|normal|hard"
+    }
+
+    fn run(&self, args: &[&str], world: &mut World) -> anyhow::Result<String> {
+        let ["difficulty", difficulty] = args else {
+            anyhow::bail!("usage: {}", self.usage());
+        };
+        let difficulty = Difficulty::from_str(difficulty, true).map_err(anyhow::Error::msg)?;
+        world.resource_mut::<MatchSetup>().difficulty = difficulty;
+        Ok(format!("AI difficulty is {:?}", difficulty))
+    }
+}
+
+/// `god <paddles>` stretches the paddles over their whole side, or shrinks
+/// them back. Picks the paddles of the players, of the AI or of a team by name.
+struct GodCommand;
+impl ConsoleCommand for GodCommand {
+    fn name(&self) -> &'static str {
+        "god"
+    }
+
+    fn usage(&self) -> &'static str {
+        "god player|ai|<team>"
+    }
+
+    fn run(&self, args: &[&str], world: &mut World) -> anyhow::Result<String> {
+        let [who] = args else {
+            anyhow::bail!("usage: {}", self.usage());
+        };
+        let team = world.resource::<Teams>().find(who);
+        let picked = |controller: &Controller, id: &TeamId| match *who {
+            "player" => *controller != Controller::Ai,
+            "ai" => *controller == Controller::Ai,
+            _ => Some(*id) == team,
+        };
+
+        let mut paddles =
+            world.query_filtered::<(Entity, &Controller, &TeamId, &Rail), With<Paddle>>();
+        let chosen: Vec<(Entity, Rail)> = paddles
+            .iter(world)
+            .filter(|(_, controller, id, _)| picked(controller, id))
+            .map(|(entity, _, _, rail)| (entity, *rail))
+            .collect();
+        if chosen.is_empty() {
+            anyhow::bail!("no paddle matches `{}`", who);
+        }
+
+        let mut messages = Vec::new();
+        for (entity, rail) in chosen {
+            let mut paddle = world.entity_mut(entity);
+            let shape = match paddle.take::<God>() {
+                Some(God(shape)) => {
+                    messages.push("mortal again");
+                    shape
+                }
+                None => {
+                    let old = paddle.get::<Shape>().cloned().unwrap();
+                    paddle.insert(God(old));
+                    let length = ARENA_SIZE.dot(rail.0);
+                    if let Some(mut position) = paddle.get_mut::<Position>() {
+                        let along = position.0.dot(rail.0);
+                        position.0 -= rail.0 * along;
+                    }
+                    if let Some(mut velocity) = paddle.get_mut::<Velocity>() {
+                        velocity.0 = Vec2::ZERO;
+                    }
+                    messages.push("god mode");
+                    if rail.is_horizontal() {
+                        Shape::Rectangle {
+                            width: length,
+                            height: WIDTH,
+                        }
+                    } else {
+                        Shape::Rectangle {
+                            width: WIDTH,
+                            height: length,
+                        }
+                    }
+                }
+            };
+            // the sprite is drawn upright and turned for the top and bottom paddles
+            if let (Shape::Rectangle { width, height }, Some(mut sprite)) =
+                (&shape, paddle.get_mut::<Sprite>())
+            {
+                sprite.custom_size = Some(Vec2::new(width.min(*height), width.max(*height)));
+            }
+            paddle.insert(shape);
+        }
+        messages.dedup();
+        Ok(messages.join(", "))
     }
 }
 
 pub struct PaddlesPlugin;
 impl Plugin for PaddlesPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, spawn);
+        let tuning: PaddleTuning = load_ron_or_default(&asset_path(TUNING_PATH));
+        app.insert_resource(tuning)
+            .register_command(AiCommand)
+            .register_command(GodCommand);
+        app.add_systems(OnEnter(GameState::Playing), spawn);
+        app.add_systems(OnEnter(GameState::Menu), despawn);
         app.add_systems(
             Update,
-            (ai_paddle, handle_input, move_paddles, project_positions).chain(),
+            (
+                attach_sprites.run_if(resource_exists::<AssetServer>),
+                retire_knocked_out.in_set(GameplaySet),
+                (drive_bots, handle_input, apply_motion, move_paddles)
+                    .chain()
+                    .in_set(GameplaySet),
+                project_positions.after(move_paddles),
+            ),
         );
     }
 }
@@ -0,0 +1,164 @@
+use bevy::{prelude::*, window::WindowFocused};
+
+use crate::{
+    game_manager::{GameState, RestartMatch},
+    settings::Settings,
+    settings_screen::SettingsScreen,
+    widgets::{
+        gamepad_pressed, highlight_focus, navigate_menu, spawn_buttons, spawn_menu, MenuFocus,
+        MenuInput,
+    },
+};
+
+/// Whether the match is frozen behind the pause menu.
+#[derive(States, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PauseState {
+    #[default]
+    Running,
+    Paused,
+}
+
+/// Entries of the pause menu, from top to bottom.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum PauseAction {
+    Resume,
+    Restart,
+    Settings,
+    Quit,
+}
+
+impl PauseAction {
+    const ALL: [PauseAction; 4] = [
+        PauseAction::Resume,
+        PauseAction::Restart,
+        PauseAction::Settings,
+        PauseAction::Quit,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            PauseAction::Resume => "Resume",
+            PauseAction::Restart => "Restart Match",
+            PauseAction::Settings => "Settings",
+            PauseAction::Quit => "Quit to Menu",
+        }
+    }
+}
+
+#[derive(Component)]
+struct PauseMenu;
+
+fn toggle_pause(
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    buttons: Res<ButtonInput<GamepadButton>>,
+    settings: Res<Settings>,
+    state: Res<State<PauseState>>,
+    mut next_state: ResMut<NextState<PauseState>>,
+) {
+    let pressed = keys.just_pressed(settings.key_bindings.pause)
+        || gamepad_pressed(&gamepads, &buttons, GamepadButtonType::Start);
+    if !pressed {
+        return;
+    }
+
+    next_state.set(match state.get() {
+        PauseState::Running => PauseState::Paused,
+        PauseState::Paused => PauseState::Running,
+    });
+}
+
+fn pause_on_focus_loss(
+    mut events: EventReader<WindowFocused>,
+    mut next_state: ResMut<NextState<PauseState>>,
+) {
+    if events.read().any(|event| !event.focused) {
+        next_state.set(PauseState::Paused);
+    }
+}
+
+fn freeze(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+fn unfreeze(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
+
+fn spawn_pause_menu(mut commands: Commands) {
+    spawn_menu(&mut commands, "PAUSED", Color::rgba(0., 0., 0., 0.6))
+        .insert(PauseMenu)
+        .with_children(|parent| {
+            spawn_buttons(parent, PauseAction::ALL.map(PauseAction::label), 32.);
+        });
+}
+
+/// Back from the settings screen, on the entry that opened it.
+fn focus_settings(mut focus: ResMut<MenuFocus>) {
+    focus.0 = PauseAction::ALL
+        .iter()
+        .position(|action| *action == PauseAction::Settings)
+        .unwrap_or_default();
+}
+
+fn despawn_menu(mut commands: Commands, menu: Query<Entity, With<PauseMenu>>) {
+    for entity in &menu {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn apply_action(
+    In(input): In<Option<MenuInput>>,
+    mut next_state: ResMut<NextState<PauseState>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut next_settings_state: ResMut<NextState<SettingsScreen>>,
+    mut restart: EventWriter<RestartMatch>,
+) {
+    let action = match input {
+        None | Some(MenuInput::Adjust(..)) => return,
+        Some(MenuInput::Back) => PauseAction::Resume,
+        Some(MenuInput::Pick(index)) => PauseAction::ALL[index],
+    };
+    match action {
+        PauseAction::Resume => next_state.set(PauseState::Running),
+        PauseAction::Restart => {
+            restart.send(RestartMatch);
+            next_state.set(PauseState::Running);
+        }
+        PauseAction::Settings => next_settings_state.set(SettingsScreen::Open),
+        PauseAction::Quit => {
+            next_state.set(PauseState::Running);
+            next_game_state.set(GameState::Menu);
+        }
+    }
+}
+
+/// Escape or Start freezes the match behind a menu, as does the window losing
+/// focus. Everything that moves runs on virtual time, which stands still
+/// while paused.
+pub struct PausePlugin;
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<PauseState>()
+            .add_systems(OnEnter(PauseState::Paused), (freeze, spawn_pause_menu))
+            .add_systems(OnExit(PauseState::Paused), (unfreeze, despawn_menu))
+            // the settings screen takes the place of the menu while it is open
+            .add_systems(OnEnter(SettingsScreen::Open), despawn_menu)
+            .add_systems(
+                OnExit(SettingsScreen::Open),
+                (spawn_pause_menu, focus_settings)
+                    .chain()
+                    .run_if(in_state(PauseState::Paused)),
+            )
+            .add_systems(
+                Update,
+                (
+                    (toggle_pause, pause_on_focus_loss).run_if(in_state(GameState::Playing)),
+                    (navigate_menu.pipe(apply_action), highlight_focus)
+                        .chain()
+                        .run_if(in_state(PauseState::Paused)),
+                )
+                    .run_if(in_state(SettingsScreen::Closed)),
+            );
+    }
+}
@@ -0,0 +1,237 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::bail;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ball::Ball,
+    border::Border,
+    game_manager::{quadrapong, unpaused, GameState, GameplaySet, MatchFinished, Score},
+    paddle::{Ai, Paddle, Player},
+    rng::GameRng,
+    team::{TeamId, Teams},
+    utils::{project_positions, read_ron, write_ron, Position, Velocity},
+};
+
+const REPLAY_VERSION: u32 = 2;
+
+/// The teams defending the right and left goals, whose paddles and points
+/// are the `player` and `ai` ones of a frame.
+fn recorded_teams(teams: &Teams) -> Option<(TeamId, TeamId)> {
+    Some((teams.owner(Border::Right)?, teams.owner(Border::Left)?))
+}
+
+/// Positions and score of one frame of a match.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ReplayFrame {
+    pub ball: [f32; 2],
+    pub player: [f32; 2],
+    pub ai: [f32; 2],
+    pub score: [u32; 2],
+}
+
+/// A recorded match, saved as RON under the platform data directory.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Replay {
+    pub version: u32,
+    /// Seed the match was played with, see `GameRng`.
+    pub seed: u64,
+    pub frames: Vec<ReplayFrame>,
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let replay: Replay = read_ron(path)?;
+        if replay.version != REPLAY_VERSION {
+            bail!(
+                "unsupported replay version {}, expected {}",
+                replay.version,
+                REPLAY_VERSION
+            );
+        }
+        if replay.frames.is_empty() {
+            bail!("the replay has no frames");
+        }
+        Ok(replay)
+    }
+}
+
+#[derive(Resource, Default)]
+struct Recorder {
+    frames: Vec<ReplayFrame>,
+}
+
+/// The replay being played back. While it is present the match is driven
+/// from the recording.
+#[derive(Resource)]
+pub struct Playback {
+    frames: Vec<ReplayFrame>,
+    index: usize,
+}
+
+impl Playback {
+    pub fn new(replay: &Replay) -> Self {
+        info!("playing back a match with seed {}", replay.seed);
+        Playback {
+            frames: replay.frames.clone(),
+            index: 0,
+        }
+    }
+}
+
+fn replays_dir() -> PathBuf {
+    dirs::data_dir()
+        .map(|dir| dir.join("bevy_pong"))
+        .unwrap_or_default()
+        .join("replays")
+}
+
+/// Replay files in the replays directory, newest first.
+pub fn saved_replays() -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(replays_dir()) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "ron"))
+        .collect();
+    // the file names end in the time the match was saved
+    paths.sort();
+    paths.reverse();
+    paths
+}
+
+fn record_frame(
+    mut recorder: ResMut<Recorder>,
+    ball: Query<&Position, With<Ball>>,
+    player: Query<&Position, With<Player>>,
+    ai: Query<&Position, With<Ai>>,
+    score: Res<Score>,
+    teams: Res<Teams>,
+) {
+    let (Ok(ball), Ok(player), Ok(ai)) = (ball.get_single(), player.get_single(), ai.get_single())
+    else {
+        return;
+    };
+    let Some((player_team, ai_team)) = recorded_teams(&teams) else {
+        return;
+    };
+
+    recorder.frames.push(ReplayFrame {
+        ball: ball.0.to_array(),
+        player: player.0.to_array(),
+        ai: ai.0.to_array(),
+        score: [score.points(player_team), score.points(ai_team)],
+    });
+}
+
+/// Drops what was recorded of a match that was left before it finished.
+fn clear_recording(mut recorder: ResMut<Recorder>) {
+    recorder.frames.clear();
+}
+
+fn save_on_match_end(
+    mut recorder: ResMut<Recorder>,
+    mut events: EventReader<MatchFinished>,
+    rng: Res<GameRng>,
+) {
+    if events.read().count() == 0 {
+        return;
+    }
+
+    let replay = Replay {
+        version: REPLAY_VERSION,
+        seed: rng.seed(),
+        frames: std::mem::take(&mut recorder.frames),
+    };
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or_default();
+    let path = replays_dir().join(format!("match-{}.ron", stamp));
+    match write_ron(&path, &replay) {
+        Ok(()) => info!("replay saved to {}", path.display()),
+        Err(err) => warn!("could not save replay to {}: {}", path.display(), err),
+    }
+}
+
+type PaddleFilter = (With<Paddle>, Without<Ball>);
+
+fn play_frame(
+    mut playback: ResMut<Playback>,
+    mut ball: Query<(&mut Position, &mut Velocity), With<Ball>>,
+    mut paddles: Query<(&mut Position, Has<Player>), PaddleFilter>,
+    mut score: ResMut<Score>,
+    teams: Res<Teams>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(frame) = playback.frames.get(playback.index) else {
+        return;
+    };
+    let Some((player_team, ai_team)) = recorded_teams(&teams) else {
+        return;
+    };
+
+    if let Ok((mut position, mut velocity)) = ball.get_single_mut() {
+        let next = Vec2::from(frame.ball);
+        velocity.0 = next - position.0;
+        position.0 = next;
+    }
+    for (mut position, is_player) in &mut paddles {
+        position.0 = Vec2::from(if is_player { frame.player } else { frame.ai });
+    }
+    if score.points(player_team) != frame.score[0] || score.points(ai_team) != frame.score[1] {
+        score.points.insert(player_team, frame.score[0]);
+        score.points.insert(ai_team, frame.score[1]);
+    }
+
+    playback.index += 1;
+    if playback.index == playback.frames.len() {
+        // back to the menu, which ends the playback
+        info!("replay finished");
+        next_state.set(GameState::Menu);
+    }
+}
+
+fn stop_playback(mut commands: Commands) {
+    commands.remove_resource::<Playback>();
+}
+
+/// Records every match to a replay file, and plays recorded ones back.
+pub struct ReplayPlugin {
+    /// Replay to play back at launch instead of a match.
+    pub replay: Option<Replay>,
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        if let Some(replay) = &self.replay {
+            app.insert_resource(Playback::new(replay));
+        }
+
+        // replays hold the two side paddles only, Quadrapong is not recorded
+        app.init_resource::<Recorder>()
+            .configure_sets(Update, GameplaySet.run_if(not(resource_exists::<Playback>)))
+            .add_systems(OnEnter(GameState::Playing), clear_recording)
+            .add_systems(OnEnter(GameState::Menu), stop_playback)
+            .add_systems(
+                Update,
+                (
+                    (
+                        record_frame
+                            .after(GameplaySet)
+                            .run_if(in_state(GameState::Playing).and_then(unpaused)),
+                        save_on_match_end,
+                    )
+                        .run_if(not(quadrapong).and_then(not(resource_exists::<Playback>))),
+                    play_frame
+                        .before(project_positions)
+                        .run_if(resource_exists::<Playback>.and_then(unpaused)),
+                ),
+            );
+    }
+}
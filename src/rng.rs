@@ -0,0 +1,25 @@
+use bevy::prelude::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+/// The single source of randomness for gameplay, so a seed reproduces a match.
+#[derive(Resource, Deref, DerefMut)]
+pub struct GameRng {
+    seed: u64,
+    #[deref]
+    rng: ChaCha8Rng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        GameRng {
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+
+    /// Seed the current match was started with.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
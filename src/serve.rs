@@ -0,0 +1,221 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ball::{serve_new_match, Ball, BallTuning, Spin},
+    game_manager::{GameState, GameplaySet, MatchSetup, Scored},
+    paddle::{move_paddles, Controller, Goal, Paddle},
+    rng::GameRng,
+    settings::Settings,
+    team::{TeamId, Teams},
+    time_scale::TimeScale,
+    utils::{Position, Shape, Velocity},
+};
+
+/// A human server has this long before the ball goes out on its own.
+const SERVE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Gap between the held ball and the paddle face.
+const HOLD_GAP: f32 = 4.;
+
+/// How the ball is put back into play after a point.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ServeMode {
+    /// The ball is launched from the middle once the countdown is over.
+    #[default]
+    Countdown,
+    /// The ball sits on the server's paddle until it is served.
+    Manual,
+}
+
+/// Who serves in `ServeMode::Manual`.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ServeRule {
+    /// The team that lost the last point serves.
+    #[default]
+    LoserServes,
+    /// The serve goes to the next team every `every` points.
+    Alternate { every: u32 },
+}
+
+/// Marks the ball while it is held by the serving paddle.
+#[derive(Component)]
+pub struct Serving {
+    pub server: TeamId,
+    /// Serves the ball automatically when finished: a timeout for humans,
+    /// a random reaction time for the AI.
+    timer: Timer,
+}
+
+#[derive(Resource)]
+struct ServeOrder {
+    first_server: TeamId,
+    points_played: u32,
+    last_loser: Option<TeamId>,
+    /// Set when the ball should be handed to the next server.
+    pending: bool,
+}
+
+impl ServeOrder {
+    fn new(rng: &mut GameRng, teams: &Teams) -> Self {
+        ServeOrder {
+            first_server: TeamId(rng.gen_range(0..teams.len())),
+            points_played: 0,
+            last_loser: None,
+            pending: true,
+        }
+    }
+
+    fn next_server(&self, rule: ServeRule, teams: &Teams) -> TeamId {
+        match (rule, self.last_loser) {
+            (ServeRule::LoserServes, Some(loser)) => loser,
+            (ServeRule::LoserServes, None) => self.first_server,
+            (ServeRule::Alternate { every }, _) => {
+                let turns = (self.points_played / every.max(1)) as usize;
+                TeamId((self.first_server.0 + turns) % teams.len())
+            }
+        }
+    }
+}
+
+fn manual_serves(setup: Res<MatchSetup>) -> bool {
+    setup.serve_mode == ServeMode::Manual
+}
+
+fn new_serve_order(mut commands: Commands, mut rng: ResMut<GameRng>, teams: Res<Teams>) {
+    commands.insert_resource(ServeOrder::new(&mut rng, &teams));
+}
+
+fn count_points(mut order: ResMut<ServeOrder>, mut events: EventReader<Scored>) {
+    for event in events.read() {
+        order.points_played += 1;
+        order.last_loser = Some(event.against);
+        order.pending = true;
+    }
+}
+
+fn hand_ball_to_server(
+    mut commands: Commands,
+    mut order: ResMut<ServeOrder>,
+    mut ball: Query<(Entity, &mut Velocity, &mut Spin), With<Ball>>,
+    paddles: Query<(&Controller, &TeamId), With<Paddle>>,
+    setup: Res<MatchSetup>,
+    teams: Res<Teams>,
+    mut rng: ResMut<GameRng>,
+) {
+    if !order.pending {
+        return;
+    }
+    let Ok((entity, mut velocity, mut spin)) = ball.get_single_mut() else {
+        return;
+    };
+
+    let server = order.next_server(setup.serve_rule, &teams);
+    let ai_serves = paddles
+        .iter()
+        .any(|(controller, team)| *controller == Controller::Ai && *team == server);
+    let delay = if ai_serves {
+        Duration::from_secs_f32(rng.gen_range(0.5..1.5))
+    } else {
+        SERVE_TIMEOUT
+    };
+
+    velocity.0 = Vec2::ZERO;
+    spin.rate = 0.;
+    commands.entity(entity).insert(Serving {
+        server,
+        timer: Timer::new(delay, TimerMode::Once),
+    });
+    order.pending = false;
+}
+
+fn hold_ball(
+    mut ball: Query<(&mut Position, &mut Velocity, &Shape, &Serving), With<Ball>>,
+    paddles: Query<(&Position, &Shape, &Goal, &TeamId), (With<Paddle>, Without<Ball>)>,
+) {
+    let Ok((mut position, mut velocity, Shape::Circle { radius }, serving)) = ball.get_single_mut()
+    else {
+        return;
+    };
+    let paddle = paddles
+        .iter()
+        .find(|(_, _, _, team)| **team == serving.server);
+    let Some((paddle_position, Shape::Rectangle { width, height }, goal, _)) = paddle else {
+        return;
+    };
+
+    // in front of the paddle face, on the side of the court
+    let towards_center = -goal.0.outward();
+    let half_depth = (towards_center * Vec2::new(*width, *height)).length() / 2.;
+    let offset = half_depth + radius + HOLD_GAP;
+    position.0 = paddle_position.0 + towards_center * offset;
+    velocity.0 = Vec2::ZERO;
+}
+
+fn release_serve(
+    mut commands: Commands,
+    mut ball: Query<(Entity, &mut Velocity, &mut Serving), With<Ball>>,
+    paddles: Query<(&Controller, &Goal, &TeamId), With<Paddle>>,
+    input: Res<ButtonInput<KeyCode>>,
+    settings: Res<Settings>,
+    tuning: Res<BallTuning>,
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    mut rng: ResMut<GameRng>,
+) {
+    let Ok((entity, mut velocity, mut serving)) = ball.get_single_mut() else {
+        return;
+    };
+
+    let keys = &settings.key_bindings;
+    let servers: Vec<_> = paddles
+        .iter()
+        .filter(|(_, _, team)| **team == serving.server)
+        .collect();
+    let Some((_, goal, _)) = servers.first() else {
+        return;
+    };
+    let key_pressed = servers.iter().any(|(controller, _, _)| match controller {
+        Controller::FirstPlayer => input.just_pressed(keys.serve),
+        Controller::SecondPlayer => input.just_pressed(keys.second_serve),
+        Controller::Ai => false,
+    });
+
+    serving.timer.tick(time_scale.scaled(time.delta()));
+    if !key_pressed && !serving.timer.finished() {
+        return;
+    }
+
+    // away from the server's goal, across the court
+    let direction = -goal.0.outward();
+    velocity.0 = tuning.serve_towards(&mut rng, direction, tuning.reset_speed);
+    commands.entity(entity).remove::<Serving>();
+}
+
+pub struct ServePlugin;
+impl Plugin for ServePlugin {
+    fn build(&self, app: &mut App) {
+        // both draw from the match's random numbers, in a fixed order to keep
+        // seeded matches reproducible
+        app.add_systems(
+            OnEnter(GameState::Playing),
+            new_serve_order.after(serve_new_match),
+        )
+        .add_systems(
+            Update,
+            (
+                count_points,
+                hand_ball_to_server,
+                hold_ball,
+                release_serve.run_if(in_state(GameState::Playing)),
+            )
+                .chain()
+                // hold the ball against where the paddle is this frame
+                .after(move_paddles)
+                .run_if(manual_serves)
+                .in_set(GameplaySet),
+        );
+    }
+}
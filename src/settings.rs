@@ -0,0 +1,276 @@
+use std::path::PathBuf;
+
+use bevy::{
+    audio::Volume,
+    prelude::*,
+    window::{PresentMode, WindowMode, WindowResolution},
+};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    clock::TimedMatch,
+    serve::{ServeMode, ServeRule},
+    utils::{read_ron, write_ron},
+};
+
+const FILE_NAME: &str = "settings.ron";
+
+#[derive(Serialize, Deserialize, ValueEnum, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+/// How fast the ball is served and how fast it may get.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum BallSpeed {
+    Slow,
+    #[default]
+    Normal,
+    Fast,
+}
+
+impl BallSpeed {
+    /// Multiplies the speeds in `assets/config/ball.ron`.
+    pub fn factor(self) -> f32 {
+        match self {
+            BallSpeed::Slow => 0.75,
+            BallSpeed::Normal => 1.,
+            BallSpeed::Fast => 1.3,
+        }
+    }
+}
+
+/// Colors the teams are told apart by.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Palette {
+    #[default]
+    Standard,
+    /// Colors that stay distinct with the common kinds of color blindness.
+    Colorblind,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub up: KeyCode,
+    pub down: KeyCode,
+    pub serve: KeyCode,
+    /// Moves a top or bottom paddle in Quadrapong.
+    pub left: KeyCode,
+    pub right: KeyCode,
+    /// Keys of the second player, who has the left paddle in local two player matches.
+    pub second_up: KeyCode,
+    pub second_down: KeyCode,
+    pub second_serve: KeyCode,
+    pub second_left: KeyCode,
+    pub second_right: KeyCode,
+    /// Pauses and resumes the match, gamepads use Start.
+    pub pause: KeyCode,
+    /// Shows and hides the debug overlay.
+    pub debug: KeyCode,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings {
+            up: KeyCode::ArrowUp,
+            down: KeyCode::ArrowDown,
+            serve: KeyCode::Space,
+            left: KeyCode::ArrowLeft,
+            right: KeyCode::ArrowRight,
+            second_up: KeyCode::KeyW,
+            second_down: KeyCode::KeyS,
+            second_serve: KeyCode::KeyD,
+            second_left: KeyCode::KeyQ,
+            second_right: KeyCode::KeyE,
+            pause: KeyCode::Escape,
+            debug: KeyCode::F3,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct VisualSettings {
+    pub ball_animation: bool,
+    pub show_countdown: bool,
+    pub palette: Palette,
+}
+
+impl Default for VisualSettings {
+    fn default() -> Self {
+        VisualSettings {
+            ball_animation: true,
+            show_countdown: true,
+            palette: Palette::default(),
+        }
+    }
+}
+
+/// User preferences, stored in `settings.ron` under the platform config directory.
+#[derive(Resource, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct Settings {
+    /// Master volume, from 0 to 1.
+    pub volume: f32,
+    /// Scale sound effects and music on top of `volume`. Nothing in the game
+    /// makes a sound yet, so they are only kept for when something does.
+    pub sfx_volume: f32,
+    pub music_volume: f32,
+    pub window_mode: WindowMode,
+    /// Size of the window in logical pixels, when not fullscreen.
+    pub resolution: (u32, u32),
+    pub vsync: bool,
+    pub difficulty: Difficulty,
+    pub target_score: u32,
+    pub ball_speed: BallSpeed,
+    pub serve_mode: ServeMode,
+    pub serve_rule: ServeRule,
+    /// Set to play timed matches instead of up to the target score.
+    pub timed: Option<TimedMatch>,
+    pub key_bindings: KeyBindings,
+    pub visuals: VisualSettings,
+    /// Fixed seed for serves and other random events, a new one is picked
+    /// on every launch when empty.
+    pub seed: Option<u64>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            volume: 1.,
+            sfx_volume: 1.,
+            music_volume: 1.,
+            window_mode: WindowMode::Windowed,
+            resolution: (1280, 720),
+            vsync: true,
+            difficulty: Difficulty::default(),
+            target_score: 5,
+            ball_speed: BallSpeed::default(),
+            serve_mode: ServeMode::default(),
+            serve_rule: ServeRule::default(),
+            timed: None,
+            key_bindings: KeyBindings::default(),
+            visuals: VisualSettings::default(),
+            seed: None,
+        }
+    }
+}
+
+pub fn settings_path() -> PathBuf {
+    dirs::config_dir()
+        .map(|dir| dir.join("bevy_pong"))
+        .unwrap_or_default()
+        .join(FILE_NAME)
+}
+
+fn save_on_change(settings: Res<Settings>) {
+    if !settings.is_changed() || settings.is_added() {
+        return;
+    }
+
+    let path = settings_path();
+    if let Err(err) = write_ron(&path, settings.as_ref()) {
+        warn!("could not save settings to {}: {}", path.display(), err);
+    }
+}
+
+fn present_mode(vsync: bool) -> PresentMode {
+    if vsync {
+        PresentMode::AutoVsync
+    } else {
+        PresentMode::AutoNoVsync
+    }
+}
+
+fn apply_settings(
+    settings: Res<Settings>,
+    mut window: Query<&mut Window>,
+    volume: Option<ResMut<GlobalVolume>>,
+    mut applied: Local<Option<Settings>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    // the window was created from the settings (and launch options) already,
+    // after that only what changed is applied so a window resized by hand
+    // keeps its size
+    let previous = applied.replace(settings.clone());
+    if let (Some(previous), Ok(mut window)) = (previous, window.get_single_mut()) {
+        if previous.window_mode != settings.window_mode {
+            window.mode = settings.window_mode;
+        }
+        if previous.resolution != settings.resolution {
+            let (width, height) = settings.resolution;
+            window.resolution.set(width as f32, height as f32);
+        }
+        if previous.vsync != settings.vsync {
+            window.present_mode = present_mode(settings.vsync);
+        }
+    }
+    if let Some(mut volume) = volume {
+        volume.volume = Volume::new(settings.volume);
+    }
+}
+
+/// Loads the settings from disk. Since the window is configured from them,
+/// this has to happen before `DefaultPlugins` are added.
+pub struct SettingsPlugin {
+    settings: Settings,
+    load_error: Option<String>,
+}
+
+impl SettingsPlugin {
+    /// Plays with `settings` instead of the saved ones.
+    pub fn new(settings: Settings) -> Self {
+        SettingsPlugin {
+            settings,
+            load_error: None,
+        }
+    }
+
+    pub fn load() -> Self {
+        let path = settings_path();
+        match read_ron(&path) {
+            Ok(settings) => SettingsPlugin {
+                settings,
+                load_error: None,
+            },
+            Err(err) => SettingsPlugin {
+                settings: Settings::default(),
+                load_error: Some(format!("could not load {}: {}", path.display(), err)),
+            },
+        }
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    pub fn window(&self) -> Window {
+        let (width, height) = self.settings.resolution;
+        Window {
+            title: "Bevy Pong".to_string(),
+            mode: self.settings.window_mode,
+            resolution: WindowResolution::new(width as f32, height as f32),
+            present_mode: present_mode(self.settings.vsync),
+            ..default()
+        }
+    }
+}
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        if let Some(err) = &self.load_error {
+            warn!("using default settings, {}", err);
+        }
+
+        app.insert_resource(self.settings.clone())
+            .add_systems(Update, (apply_settings, save_on_change));
+    }
+}
@@ -0,0 +1,217 @@
+use bevy::{prelude::*, window::WindowMode};
+
+use crate::{
+    settings::{BallSpeed, Difficulty, Palette, Settings},
+    widgets::{highlight_focus, navigate_menu, spawn_buttons, spawn_menu, MenuButton, MenuInput},
+};
+
+const RESOLUTIONS: [(u32, u32); 4] = [(960, 540), (1280, 720), (1600, 900), (1920, 1080)];
+const MAX_TARGET_SCORE: u32 = 21;
+
+/// Whether the settings screen is open, on top of the main or the pause menu.
+#[derive(States, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SettingsScreen {
+    #[default]
+    Closed,
+    Open,
+}
+
+/// Rows of the settings screen, from top to bottom.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Row {
+    WindowMode,
+    Resolution,
+    Vsync,
+    MasterVolume,
+    SfxVolume,
+    MusicVolume,
+    Difficulty,
+    TargetScore,
+    BallSpeed,
+    Palette,
+    Back,
+}
+
+impl Row {
+    const ALL: [Row; 11] = [
+        Row::WindowMode,
+        Row::Resolution,
+        Row::Vsync,
+        Row::MasterVolume,
+        Row::SfxVolume,
+        Row::MusicVolume,
+        Row::Difficulty,
+        Row::TargetScore,
+        Row::BallSpeed,
+        Row::Palette,
+        Row::Back,
+    ];
+
+    fn label(self, settings: &Settings) -> String {
+        let percent = |volume: f32| format!("{:.0}%", volume * 100.);
+        match self {
+            Row::WindowMode => format!(
+                "Window: {}",
+                match settings.window_mode {
+                    WindowMode::Windowed => "Windowed",
+                    WindowMode::BorderlessFullscreen => "Borderless",
+                    WindowMode::Fullscreen | WindowMode::SizedFullscreen => "Fullscreen",
+                }
+            ),
+            Row::Resolution => {
+                let (width, height) = settings.resolution;
+                format!("Resolution: {}x{}", width, height)
+            }
+            Row::Vsync => format!("VSync: {}", if settings.vsync { "On" } else { "Off" }),
+            Row::MasterVolume => format!("Volume: {}", percent(settings.volume)),
+            Row::SfxVolume => format!("SFX: {}", percent(settings.sfx_volume)),
+            Row::MusicVolume => format!("Music: {}", percent(settings.music_volume)),
+            Row::Difficulty => format!("AI: {:?}", settings.difficulty),
+            Row::TargetScore => format!("Target Score: {}", settings.target_score),
+            Row::BallSpeed => format!("Ball Speed: {:?}", settings.ball_speed),
+            Row::Palette => format!("Colors: {:?}", settings.visuals.palette),
+            Row::Back => "Back".to_string(),
+        }
+    }
+
+    /// Moves the value of the row `step` options on.
+    fn adjust(self, settings: &mut Settings, step: i32) {
+        let volume = |volume: f32| (volume + 0.1 * step as f32).clamp(0., 1.);
+        match self {
+            Row::WindowMode => {
+                settings.window_mode = cycle(
+                    &[
+                        WindowMode::Windowed,
+                        WindowMode::BorderlessFullscreen,
+                        WindowMode::Fullscreen,
+                    ],
+                    settings.window_mode,
+                    step,
+                );
+            }
+            Row::Resolution => {
+                settings.resolution = cycle(&RESOLUTIONS, settings.resolution, step);
+            }
+            Row::Vsync => settings.vsync = !settings.vsync,
+            Row::MasterVolume => settings.volume = volume(settings.volume),
+            Row::SfxVolume => settings.sfx_volume = volume(settings.sfx_volume),
+            Row::MusicVolume => settings.music_volume = volume(settings.music_volume),
+            Row::Difficulty => {
+                settings.difficulty = cycle(
+                    &[Difficulty::Easy, Difficulty::Normal, Difficulty::Hard],
+                    settings.difficulty,
+                    step,
+                );
+            }
+            Row::TargetScore => {
+                settings.target_score = settings
+                    .target_score
+                    .saturating_add_signed(step)
+                    .clamp(1, MAX_TARGET_SCORE);
+            }
+            Row::BallSpeed => {
+                settings.ball_speed = cycle(
+                    &[BallSpeed::Slow, BallSpeed::Normal, BallSpeed::Fast],
+                    settings.ball_speed,
+                    step,
+                );
+            }
+            Row::Palette => {
+                settings.visuals.palette = cycle(
+                    &[Palette::Standard, Palette::Colorblind],
+                    settings.visuals.palette,
+                    step,
+                );
+            }
+            Row::Back => {}
+        }
+    }
+}
+
+/// The option `step` places after `current`, wrapping around. Values that are
+/// not among the options, such as a resolution typed into the settings file,
+/// start over from the first one.
+fn cycle<T: Copy + PartialEq>(options: &[T], current: T, step: i32) -> T {
+    let count = options.len() as i32;
+    let index = match options.iter().position(|option| *option == current) {
+        Some(index) => (index as i32 + step).rem_euclid(count),
+        None => 0,
+    };
+    options[index as usize]
+}
+
+#[derive(Component)]
+struct SettingsMenu;
+
+fn spawn_settings_menu(mut commands: Commands, settings: Res<Settings>) {
+    let labels: Vec<String> = Row::ALL.iter().map(|row| row.label(&settings)).collect();
+    spawn_menu(&mut commands, "SETTINGS", Color::rgb(0.05, 0.05, 0.05))
+        .insert(SettingsMenu)
+        .with_children(|parent| {
+            // smaller than the other menus to fit every row on a 720p window
+            spawn_buttons(parent, labels.iter().map(String::as_str), 22.);
+        });
+}
+
+fn despawn_settings_menu(mut commands: Commands, menu: Query<Entity, With<SettingsMenu>>) {
+    for entity in &menu {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn apply_input(
+    In(input): In<Option<MenuInput>>,
+    mut settings: ResMut<Settings>,
+    mut next_state: ResMut<NextState<SettingsScreen>>,
+) {
+    match input {
+        None => {}
+        Some(MenuInput::Back) => next_state.set(SettingsScreen::Closed),
+        Some(MenuInput::Pick(index)) => match Row::ALL[index] {
+            Row::Back => next_state.set(SettingsScreen::Closed),
+            row => row.adjust(&mut settings, 1),
+        },
+        Some(MenuInput::Adjust(index, step)) => match Row::ALL[index] {
+            Row::Back => {}
+            row => row.adjust(&mut settings, step),
+        },
+    }
+}
+
+fn update_labels(
+    settings: Res<Settings>,
+    buttons: Query<(&MenuButton, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    for (button, children) in &buttons {
+        let Some(row) = Row::ALL.get(button.0) else {
+            continue;
+        };
+        for child in children {
+            if let Ok(mut text) = texts.get_mut(*child) {
+                text.sections[0].value = row.label(&settings);
+            }
+        }
+    }
+}
+
+/// Edits the settings from the main and pause menus. Every change is applied
+/// right away where the game can, and saved by `SettingsPlugin`.
+pub struct SettingsScreenPlugin;
+impl Plugin for SettingsScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<SettingsScreen>()
+            .add_systems(OnEnter(SettingsScreen::Open), spawn_settings_menu)
+            .add_systems(OnExit(SettingsScreen::Open), despawn_settings_menu)
+            .add_systems(
+                Update,
+                (
+                    navigate_menu.pipe(apply_input),
+                    highlight_focus,
+                    update_labels.run_if(resource_changed::<Settings>),
+                )
+                    .chain()
+                    .run_if(in_state(SettingsScreen::Open)),
+            );
+    }
+}
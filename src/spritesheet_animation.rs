@@ -1,5 +1,7 @@
 use bevy::prelude::*;
 
+use crate::{settings::Settings, time_scale::TimeScale};
+
 #[derive(Component)]
 pub struct AnimationIndices {
     pub first: usize,
@@ -11,10 +13,11 @@ pub struct AnimationTimer(pub Timer);
 
 fn animate_sprite(
     time: Res<Time>,
+    time_scale: Res<TimeScale>,
     mut query: Query<(&AnimationIndices, &mut AnimationTimer, &mut TextureAtlas)>,
 ) {
     for (indices, mut timer, mut atlas) in &mut query {
-        timer.tick(time.delta());
+        timer.tick(time_scale.scaled(time.delta()));
         if timer.just_finished() {
             atlas.index = if atlas.index == indices.last {
                 indices.first
@@ -28,6 +31,9 @@ fn animate_sprite(
 pub struct SpritesheetAnimationPlugin;
 impl Plugin for SpritesheetAnimationPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, animate_sprite);
+        app.add_systems(
+            Update,
+            animate_sprite.run_if(|settings: Res<Settings>| settings.visuals.ball_animation),
+        );
     }
 }
@@ -0,0 +1,361 @@
+use std::{
+    fs,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use serde::Serialize;
+
+use crate::{
+    ball::{Ball, BallCollision},
+    game_manager::{
+        countdown_guard, AllowedToRun, GameState, GameplaySet, MatchSetup, Restarting, Score,
+        Scored,
+    },
+    paddle::Paddle,
+    team::{TeamId, Teams, WinRule},
+    time_scale::TimeScale,
+    utils::{format_duration, Position, Velocity},
+};
+
+#[derive(Serialize, Default, Clone, Debug)]
+pub struct SideStats {
+    pub team: String,
+    pub hits: u32,
+    /// Seconds the ball spent in this side's half of the court.
+    pub possession_secs: f32,
+    pub points_on_serve: u32,
+}
+
+/// Statistics of the current match. They are kept after the match ends
+/// for the summary screen, and reset when the next match starts.
+#[derive(Resource, Serialize, Default, Clone, Debug)]
+pub struct MatchStats {
+    /// Indexed by `TeamId`.
+    pub teams: Vec<SideStats>,
+    /// Seconds the ball was in play, countdowns excluded.
+    pub duration_secs: f32,
+    /// Paddle hits in each finished rally.
+    pub rallies: Vec<u32>,
+    pub longest_rally: u32,
+    pub peak_speed: f32,
+    speed_total: f32,
+    speed_samples: u32,
+    rally: u32,
+    #[serde(skip)]
+    server: Option<TeamId>,
+}
+
+impl MatchStats {
+    fn new(teams: &Teams) -> Self {
+        MatchStats {
+            teams: teams
+                .iter()
+                .map(|(_, team)| SideStats {
+                    team: team.name.clone(),
+                    ..default()
+                })
+                .collect(),
+            ..default()
+        }
+    }
+
+    pub fn average_rally(&self) -> f32 {
+        if self.rallies.is_empty() {
+            return 0.;
+        }
+        self.rallies.iter().sum::<u32>() as f32 / self.rallies.len() as f32
+    }
+
+    pub fn average_speed(&self) -> f32 {
+        if self.speed_samples == 0 {
+            return 0.;
+        }
+        self.speed_total / self.speed_samples as f32
+    }
+
+    fn side_mut(&mut self, team: TeamId) -> &mut SideStats {
+        &mut self.teams[team.0]
+    }
+
+    /// Share of the possession the side had, in percent, 0 before the ball
+    /// was in play.
+    fn possession_pct(&self, side: &SideStats) -> f32 {
+        let total: f32 = self.teams.iter().map(|side| side.possession_secs).sum();
+        if total > 0. {
+            100. * side.possession_secs / total
+        } else {
+            0.
+        }
+    }
+
+    /// A `stat,<team>...` section with a column per team, then a blank line
+    /// and a `stat,value` section with the totals of the match.
+    fn to_csv(&self) -> String {
+        let row = |name: &str, value: &dyn Fn(&SideStats) -> String| {
+            let values: Vec<String> = self.teams.iter().map(value).collect();
+            format!("{},{}\n", name, values.join(","))
+        };
+        let totals = [
+            ("duration_secs", format!("{:.2}", self.duration_secs)),
+            ("longest_rally", self.longest_rally.to_string()),
+            ("average_rally", format!("{:.2}", self.average_rally())),
+            ("average_speed", format!("{:.2}", self.average_speed())),
+            ("peak_speed", format!("{:.2}", self.peak_speed)),
+        ];
+
+        let mut csv = row("stat", &|side| side.team.clone());
+        csv += &row("hits", &|side| side.hits.to_string());
+        csv += &row("possession_secs", &|side| {
+            format!("{:.2}", side.possession_secs)
+        });
+        csv += &row("possession_pct", &|side| {
+            format!("{:.1}", self.possession_pct(side))
+        });
+        csv += &row("points_on_serve", &|side| side.points_on_serve.to_string());
+
+        csv += "\nstat,value\n";
+        for (name, value) in totals {
+            csv += &format!("{},{}\n", name, value);
+        }
+        csv
+    }
+}
+
+/// The team defending the goal `direction` points to the most.
+fn team_towards(direction: Vec2, teams: &Teams) -> Option<TeamId> {
+    teams
+        .iter()
+        .flat_map(|(id, team)| team.goals.iter().map(move |side| (id, *side)))
+        .max_by(|(_, a), (_, b)| {
+            direction
+                .dot(a.outward())
+                .total_cmp(&direction.dot(b.outward()))
+        })
+        .map(|(id, _)| id)
+}
+
+#[derive(Component)]
+struct SummaryScreen;
+
+#[derive(Component)]
+struct SummaryText;
+
+fn export_dir() -> PathBuf {
+    dirs::data_dir()
+        .map(|dir| dir.join("bevy_pong"))
+        .unwrap_or_default()
+        .join("stats")
+}
+
+/// Writes the stats as both JSON and CSV, returning the directory they were written to.
+fn export(stats: &MatchStats) -> anyhow::Result<PathBuf> {
+    let dir = export_dir();
+    fs::create_dir_all(&dir)?;
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    fs::write(
+        dir.join(format!("match-{}.json", stamp)),
+        serde_json::to_string_pretty(stats)?,
+    )?;
+    fs::write(dir.join(format!("match-{}.csv", stamp)), stats.to_csv())?;
+    Ok(dir)
+}
+
+fn track_rallies(
+    mut stats: ResMut<MatchStats>,
+    mut collisions: EventReader<BallCollision>,
+    mut scored: EventReader<Scored>,
+    paddles: Query<&TeamId, With<Paddle>>,
+) {
+    for event in collisions.read() {
+        let Ok(team) = paddles.get(event.entity) else {
+            continue;
+        };
+        stats.side_mut(*team).hits += 1;
+        stats.rally += 1;
+        stats.longest_rally = stats.longest_rally.max(stats.rally);
+    }
+
+    for event in scored.read() {
+        let rally = stats.rally;
+        stats.rallies.push(rally);
+        stats.rally = 0;
+
+        if let Some(server) = stats.server.filter(|server| *server != event.against) {
+            stats.side_mut(server).points_on_serve += 1;
+        }
+        stats.server = None;
+    }
+}
+
+fn track_ball(
+    In(allowed): In<AllowedToRun>,
+    mut stats: ResMut<MatchStats>,
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    ball: Query<(&Position, &Velocity), With<Ball>>,
+    teams: Res<Teams>,
+) {
+    if !allowed {
+        return;
+    }
+
+    let delta = time_scale.scaled(time.delta()).as_secs_f32();
+    stats.duration_secs += delta;
+
+    let Ok((position, velocity)) = ball.get_single() else {
+        return;
+    };
+    let speed = velocity.0.length();
+    if speed == 0. {
+        return;
+    }
+
+    // the first movement of a point tells who served it: the ball moves away from the server
+    if stats.server.is_none() {
+        stats.server = team_towards(-velocity.0, &teams);
+    }
+
+    stats.speed_total += speed;
+    stats.speed_samples += 1;
+    stats.peak_speed = stats.peak_speed.max(speed);
+
+    // every team holds the part of the court in front of its goal
+    if let Some(team) = team_towards(position.0, &teams) {
+        stats.side_mut(team).possession_secs += delta;
+    }
+}
+
+fn summary_report(stats: &MatchStats, score: &Score, teams: &Teams, target_score: u32) -> String {
+    let winner = score
+        .leader(teams, target_score)
+        .map_or("nobody", |team| teams.get(team).name.as_str());
+    let headline = match teams.rule {
+        WinRule::FirstTo | WinRule::Endless => "Winner",
+        WinRule::LastStanding => "Last one standing",
+    };
+    let per_team = |value: &dyn Fn(&SideStats) -> String| {
+        stats
+            .teams
+            .iter()
+            .map(|side| format!("{} {}", side.team, value(side)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    format!(
+        "{}: {}  {}\n\nduration {}\nlongest rally {}, average rally {:.1}\nhits: {}\nball speed: average {:.1}, peak {:.1}\npossession: {}\npoints won on serve: {}\n\n[Space] next match   [E] export stats",
+        headline,
+        winner,
+        score.summary(teams, target_score),
+        format_duration(stats.duration_secs),
+        stats.longest_rally,
+        stats.average_rally(),
+        per_team(&|side| side.hits.to_string()),
+        stats.average_speed(),
+        stats.peak_speed,
+        per_team(&|side| format!("{:.0}%", stats.possession_pct(side))),
+        per_team(&|side| side.points_on_serve.to_string()),
+    )
+}
+
+fn spawn_summary(
+    mut commands: Commands,
+    stats: Res<MatchStats>,
+    score: Res<Score>,
+    teams: Res<Teams>,
+    setup: Res<MatchSetup>,
+) {
+    let report = summary_report(&stats, &score, &teams, setup.target_score);
+
+    commands
+        .spawn((
+            SummaryScreen,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.7).into(),
+                z_index: ZIndex::Global(5),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                SummaryText,
+                TextBundle::from_section(
+                    report,
+                    TextStyle {
+                        font_size: 30.,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+            ));
+        });
+}
+
+fn despawn_summary(mut commands: Commands, screen: Query<Entity, With<SummaryScreen>>) {
+    for entity in &screen {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn handle_summary_input(
+    input: Res<ButtonInput<KeyCode>>,
+    stats: Res<MatchStats>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut text: Query<&mut Text, With<SummaryText>>,
+) {
+    if input.just_pressed(KeyCode::Space) {
+        next_state.set(GameState::Playing);
+    }
+
+    if input.just_pressed(KeyCode::KeyE) {
+        let message = match export(&stats) {
+            Ok(dir) => format!("\n\nstats exported to {}", dir.display()),
+            Err(err) => format!("\n\ncould not export stats: {}", err),
+        };
+        if let Ok(mut text) = text.get_single_mut() {
+            text.sections[0].value += &message;
+        }
+    }
+}
+
+fn reset_stats(mut stats: ResMut<MatchStats>, teams: Res<Teams>) {
+    *stats = MatchStats::new(&teams);
+}
+
+pub struct StatsPlugin;
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        let stats = MatchStats::new(app.world.resource::<Teams>());
+        app.insert_resource(stats)
+            .add_systems(
+                OnEnter(GameState::MatchOver),
+                spawn_summary.run_if(not(resource_exists::<Restarting>)),
+            )
+            .add_systems(OnExit(GameState::MatchOver), despawn_summary)
+            .add_systems(OnEnter(GameState::Playing), reset_stats)
+            .add_systems(
+                Update,
+                (
+                    // after the point is scored, so the rally that ends the
+                    // match is counted before it is over
+                    track_rallies
+                        .after(GameplaySet)
+                        .run_if(in_state(GameState::Playing)),
+                    countdown_guard
+                        .pipe(track_ball)
+                        .run_if(in_state(GameState::Playing))
+                        .in_set(GameplaySet),
+                    handle_summary_input.run_if(in_state(GameState::MatchOver)),
+                ),
+            );
+    }
+}
@@ -0,0 +1,134 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{border::Border, game_manager::GameMode, settings::Palette};
+
+/// Index of a team in the `Teams` registry.
+#[derive(Component, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct TeamId(pub usize);
+
+/// Marks a border as the goal of a team: the ball reaching it is a goal against the team.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct TeamGoal(pub TeamId);
+
+#[derive(Clone, Debug)]
+pub struct Team {
+    pub name: String,
+    pub color: Color,
+    /// Borders the team defends, one paddle each.
+    pub goals: Vec<Border>,
+}
+
+/// Color of the team defending `side`.
+fn side_color(side: Border, palette: Palette) -> Color {
+    match (palette, side) {
+        (_, Border::Left) => Color::BLACK,
+        (Palette::Standard, Border::Right) => Color::GREEN,
+        (Palette::Standard, Border::Top) => Color::BLUE,
+        (Palette::Standard, Border::Bottom) => Color::RED,
+        // sky blue, orange and yellow from the Okabe-Ito palette
+        (Palette::Colorblind, Border::Right) => Color::rgb(0.34, 0.71, 0.91),
+        (Palette::Colorblind, Border::Top) => Color::rgb(0.9, 0.62, 0.),
+        (Palette::Colorblind, Border::Bottom) => Color::rgb(0.94, 0.89, 0.26),
+    }
+}
+
+impl Team {
+    fn new(name: &str, goals: &[Border], palette: Palette) -> Self {
+        Team {
+            name: name.to_string(),
+            color: side_color(goals[0], palette),
+            goals: goals.to_vec(),
+        }
+    }
+}
+
+/// How the match is won.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WinRule {
+    /// Every goal is a point for each of the other teams, the first to the
+    /// target score wins.
+    FirstTo,
+    /// A team is out once it has let in the target score, the last one left wins.
+    LastStanding,
+    /// Nobody wins, the goals let in are all that is counted.
+    Endless,
+}
+
+/// The teams of the current match, listed from left to right on the scoreboard.
+#[derive(Resource, Clone, Debug)]
+pub struct Teams {
+    teams: Vec<Team>,
+    pub rule: WinRule,
+}
+
+impl Teams {
+    pub fn for_mode(mode: GameMode, palette: Palette) -> Self {
+        let two_sided = |left: &str, right: &str| Teams {
+            teams: vec![
+                Team::new(left, &[Border::Left], palette),
+                Team::new(right, &[Border::Right], palette),
+            ],
+            rule: WinRule::FirstTo,
+        };
+
+        match mode {
+            GameMode::VsAi => two_sided("ai", "you"),
+            GameMode::LocalTwoPlayer => two_sided("left", "right"),
+            GameMode::AiVsAi => two_sided("left ai", "right ai"),
+            // the left border is a plain wall to play against
+            GameMode::Practice => Teams {
+                teams: vec![Team::new("you", &[Border::Right], palette)],
+                rule: WinRule::Endless,
+            },
+            GameMode::Quadrapong => Teams {
+                teams: vec![
+                    Team::new("left", &[Border::Left], palette),
+                    Team::new("right", &[Border::Right], palette),
+                    Team::new("top", &[Border::Top], palette),
+                    Team::new("bottom", &[Border::Bottom], palette),
+                ],
+                rule: WinRule::LastStanding,
+            },
+        }
+    }
+
+    pub fn recolor(&mut self, palette: Palette) {
+        for team in &mut self.teams {
+            team.color = side_color(team.goals[0], palette);
+        }
+    }
+
+    /// The team called `name`.
+    pub fn find(&self, name: &str) -> Option<TeamId> {
+        self.iter()
+            .find(|(_, team)| team.name == name)
+            .map(|(id, _)| id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.teams.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.teams.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (TeamId, &Team)> {
+        self.teams
+            .iter()
+            .enumerate()
+            .map(|(index, team)| (TeamId(index), team))
+    }
+
+    pub fn get(&self, id: TeamId) -> &Team {
+        &self.teams[id.0]
+    }
+
+    /// The team defending `border`, if it is a goal at all.
+    pub fn owner(&self, border: Border) -> Option<TeamId> {
+        self.iter()
+            .find(|(_, team)| team.goals.contains(&border))
+            .map(|(id, _)| id)
+    }
+}
@@ -0,0 +1,154 @@
+use std::time::Duration;
+
+use anyhow::bail;
+use bevy::prelude::*;
+
+use crate::console::{ConsoleCommand, RegisterCommand};
+
+/// Slow motion speeds the debug hotkey goes through.
+#[cfg(debug_assertions)]
+const SLOW_MOTION: [f32; 3] = [1., 0.5, 0.25];
+
+/// How fast the simulation runs, applied to the movement of the ball and the
+/// paddles, the countdown, the serve timeout, the match clock, the match
+/// stats and the sprite animations. Velocities are per frame, so a scale of
+/// 0.5 moves everything half as far every frame.
+///
+/// The contacts of the debug overlay fade on real time, so they stay readable
+/// in slow motion.
+#[derive(Resource, Debug)]
+pub struct TimeScale {
+    pub scale: f32,
+    /// Holds the simulation still, short of stepping it a frame at a time.
+    pub held: bool,
+    /// A step asked for this frame, taken on the next one.
+    step_requested: bool,
+    /// Lets this frame through while held.
+    stepping: bool,
+}
+
+impl Default for TimeScale {
+    fn default() -> Self {
+        TimeScale {
+            scale: 1.,
+            held: false,
+            step_requested: false,
+            stepping: false,
+        }
+    }
+}
+
+impl TimeScale {
+    /// Whether the simulation moves this frame.
+    pub fn running(&self) -> bool {
+        !self.held || self.stepping
+    }
+
+    /// What per frame amounts are multiplied by, 0 while held.
+    pub fn factor(&self) -> f32 {
+        if self.running() {
+            self.scale
+        } else {
+            0.
+        }
+    }
+
+    /// `delta` as it passes for the simulation.
+    pub fn scaled(&self, delta: Duration) -> Duration {
+        delta.mul_f32(self.factor())
+    }
+
+    /// Runs the next frame, and holds the simulation after it.
+    pub fn step(&mut self) {
+        self.held = true;
+        self.step_requested = true;
+    }
+}
+
+/// Run condition that is false while the simulation is held.
+pub fn simulation_running(time_scale: Res<TimeScale>) -> bool {
+    time_scale.running()
+}
+
+/// Steps are taken a whole frame at a time, however late in the frame they
+/// were asked for.
+fn begin_step(mut time_scale: ResMut<TimeScale>) {
+    if time_scale.step_requested || time_scale.stepping {
+        time_scale.stepping = std::mem::take(&mut time_scale.step_requested);
+    }
+}
+
+/// F6 holds and releases the simulation, F7 steps it a frame and F8 goes
+/// through the slow motion speeds.
+#[cfg(debug_assertions)]
+fn debug_hotkeys(keys: Res<ButtonInput<KeyCode>>, mut time_scale: ResMut<TimeScale>) {
+    if keys.just_pressed(KeyCode::F6) {
+        time_scale.held = !time_scale.held;
+    }
+    if keys.just_pressed(KeyCode::F7) {
+        time_scale.step();
+    }
+    if keys.just_pressed(KeyCode::F8) {
+        let next = SLOW_MOTION
+            .iter()
+            .position(|scale| *scale == time_scale.scale)
+            .map_or(0, |index| (index + 1) % SLOW_MOTION.len());
+        time_scale.scale = SLOW_MOTION[next];
+    }
+}
+
+/// `timescale <factor>` sets the speed of the simulation and lets it run.
+struct TimescaleCommand;
+impl ConsoleCommand for TimescaleCommand {
+    fn name(&self) -> &'static str {
+        "timescale"
+    }
+
+    fn usage(&self) -> &'static str {
+        "timescale <factor>"
+    }
+
+    fn run(&self, args: &[&str], world: &mut World) -> anyhow::Result<String> {
+        let [factor] = args else {
+            bail!("usage: {}", self.usage());
+        };
+        let factor: f32 = factor.parse()?;
+        if !factor.is_finite() || factor < 0. {
+            bail!("the time scale can't be negative");
+        }
+        let mut time_scale = world.resource_mut::<TimeScale>();
+        time_scale.scale = factor;
+        time_scale.held = false;
+        Ok(format!("the simulation runs at {}x", factor))
+    }
+}
+
+/// `step` holds the simulation, then moves it on one frame each time.
+struct StepCommand;
+impl ConsoleCommand for StepCommand {
+    fn name(&self) -> &'static str {
+        "step"
+    }
+
+    fn usage(&self) -> &'static str {
+        "step"
+    }
+
+    fn run(&self, _: &[&str], world: &mut World) -> anyhow::Result<String> {
+        world.resource_mut::<TimeScale>().step();
+        Ok("stepped one frame, `timescale 1` to resume".to_string())
+    }
+}
+
+pub struct TimeScalePlugin;
+impl Plugin for TimeScalePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TimeScale>()
+            .add_systems(First, begin_step)
+            .register_command(TimescaleCommand)
+            .register_command(StepCommand);
+
+        #[cfg(debug_assertions)]
+        app.add_systems(Update, debug_hotkeys);
+    }
+}
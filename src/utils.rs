@@ -1,7 +1,14 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
 use bevy::{
+    asset::io::file::FileAssetReader,
     math::bounding::{Aabb2d, BoundingCircle, BoundingVolume, IntersectsVolume},
     prelude::*,
 };
+use serde::{de::DeserializeOwned, Serialize};
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub enum Collision {
@@ -23,31 +30,153 @@ pub enum Shape {
     Rectangle { width: f32, height: f32 },
 }
 
-pub fn ball_collision(ball: BoundingCircle, wall: Aabb2d) -> Option<Collision> {
+#[derive(Debug, Copy, Clone)]
+pub struct Contact {
+    pub side: Collision,
+    /// Unit vector pointing from the wall towards the ball.
+    pub normal: Vec2,
+    /// How deep the ball is inside the wall along `normal`.
+    pub penetration: f32,
+}
+
+pub fn ball_collision(ball: BoundingCircle, wall: Aabb2d) -> Option<Contact> {
     if !ball.intersects(&wall) {
         return None;
     }
 
-    let closest_point = wall.closest_point(ball.center());
-    let offset = ball.center() - closest_point;
+    let center = ball.center();
+    let closest_point = wall.closest_point(center);
+    let offset = center - closest_point;
+    let distance = offset.length();
 
-    let side = if offset.x.abs() > offset.y.abs() {
-        if offset.x < 0. {
+    let (normal, penetration) = if distance > f32::EPSILON {
+        (offset / distance, ball.radius() - distance)
+    } else {
+        // the center is inside the wall, push out through the nearest face
+        let to_min = center - wall.min;
+        let to_max = wall.max - center;
+        let faces = [
+            (Vec2::NEG_X, to_min.x),
+            (Vec2::X, to_max.x),
+            (Vec2::NEG_Y, to_min.y),
+            (Vec2::Y, to_max.y),
+        ];
+        let (normal, depth) = faces
+            .into_iter()
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap();
+        (normal, ball.radius() + depth)
+    };
+
+    let side = if normal.x.abs() > normal.y.abs() {
+        if normal.x < 0. {
             Collision::Left
         } else {
             Collision::Right
         }
-    } else if offset.y > 0. {
+    } else if normal.y > 0. {
         Collision::Top
     } else {
         Collision::Bottom
     };
 
-    Some(side)
+    Some(Contact {
+        side,
+        normal,
+        penetration,
+    })
 }
 
+/// Size of the court in world units, the same on every display. The camera
+/// scales it to fit the window.
+pub const ARENA_SIZE: Vec2 = Vec2::new(1280., 720.);
+
 pub fn project_positions(mut positionables: Query<(&mut Transform, &Position)>) {
     for (mut transform, position) in &mut positionables {
         transform.translation = position.0.extend(0.);
     }
 }
+
+/// Reads and parses a RON file.
+pub fn read_ron<T: DeserializeOwned>(path: &Path) -> anyhow::Result<T> {
+    let text = fs::read_to_string(path)?;
+    Ok(ron::from_str(&text)?)
+}
+
+/// Writes a value as pretty RON, creating the parent directory if needed.
+pub fn write_ron<T: Serialize>(path: &Path, value: &T) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let text = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())?;
+    fs::write(path, text)?;
+    Ok(())
+}
+
+/// Reads a RON config file, falling back to the defaults with a warning
+/// when the file is missing or can't be parsed.
+pub fn load_ron_or_default<T: DeserializeOwned + Default>(path: &Path) -> T {
+    read_ron(path).unwrap_or_else(|err| {
+        warn!("using defaults, could not load {}: {}", path.display(), err);
+        T::default()
+    })
+}
+
+/// Resolves a path under `assets/` the way the asset server does, so config
+/// files are found no matter which directory the game is started from.
+pub fn asset_path(relative: &str) -> PathBuf {
+    FileAssetReader::get_base_path()
+        .join("assets")
+        .join(relative)
+}
+
+/// Formats a number of seconds as `m:ss`.
+pub fn format_duration(secs: f32) -> String {
+    let secs = secs as u32;
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A paddle sized wall centered on the origin.
+    fn wall() -> Aabb2d {
+        Aabb2d::new(Vec2::ZERO, Vec2::new(10., 50.))
+    }
+
+    #[test]
+    fn misses_a_wall_out_of_reach() {
+        let ball = BoundingCircle::new(Vec2::new(20., 0.), 5.);
+        assert!(ball_collision(ball, wall()).is_none());
+    }
+
+    #[test]
+    fn face_hit_pushes_out_along_the_face_normal() {
+        let ball = BoundingCircle::new(Vec2::new(14., 0.), 5.);
+        let contact = ball_collision(ball, wall()).unwrap();
+        assert_eq!(contact.side, Collision::Right);
+        assert_eq!(contact.normal, Vec2::X);
+        assert!((contact.penetration - 1.).abs() < 1e-5);
+    }
+
+    #[test]
+    fn corner_hit_pushes_out_diagonally() {
+        let ball = BoundingCircle::new(Vec2::new(13., 53.), 5.);
+        let contact = ball_collision(ball, wall()).unwrap();
+        assert!(contact.normal.abs_diff_eq(Vec2::ONE.normalize(), 1e-5));
+        assert!((contact.penetration - (5. - 18f32.sqrt())).abs() < 1e-5);
+        // resting on the corner after the push
+        let pushed = ball.center() + contact.normal * contact.penetration;
+        assert!(((pushed - Vec2::new(10., 50.)).length() - 5.).abs() < 1e-4);
+    }
+
+    #[test]
+    fn center_inside_pushes_out_through_the_nearest_face() {
+        let ball = BoundingCircle::new(Vec2::new(8., -30.), 5.);
+        let contact = ball_collision(ball, wall()).unwrap();
+        assert_eq!(contact.side, Collision::Right);
+        assert_eq!(contact.normal, Vec2::X);
+        assert!((contact.penetration - 7.).abs() < 1e-5);
+    }
+}
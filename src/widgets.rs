@@ -0,0 +1,193 @@
+use bevy::{ecs::system::EntityCommands, prelude::*, ui::FocusPolicy};
+
+const BUTTON_COLOR: Color = Color::rgb(0.15, 0.15, 0.15);
+const FOCUSED_BUTTON_COLOR: Color = Color::rgb(0.35, 0.35, 0.35);
+
+/// Index of the highlighted button of the open menu.
+#[derive(Resource, Default)]
+pub struct MenuFocus(pub usize);
+
+/// A menu button, numbered from the top.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct MenuButton(pub usize);
+
+/// Line of text under the buttons, for messages about the last choice.
+#[derive(Component)]
+pub struct MenuHint;
+
+/// What the player did in a menu this frame.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MenuInput {
+    /// The button with this index was picked.
+    Pick(usize),
+    /// Leave the menu, or go up a level.
+    Back,
+    /// Step the value of the button with this index down or up.
+    Adjust(usize, i32),
+}
+
+/// Whether `button` was just pressed on any connected gamepad.
+pub fn gamepad_pressed(
+    gamepads: &Gamepads,
+    buttons: &ButtonInput<GamepadButton>,
+    button: GamepadButtonType,
+) -> bool {
+    gamepads
+        .iter()
+        .any(|gamepad| buttons.just_pressed(GamepadButton::new(gamepad, button)))
+}
+
+/// A full screen overlay with `title` on top, ready for buttons. Resets the focus
+/// to the first button.
+pub fn spawn_menu<'a>(
+    commands: &'a mut Commands,
+    title: &str,
+    background: Color,
+) -> EntityCommands<'a> {
+    commands.insert_resource(MenuFocus::default());
+    let mut menu = commands.spawn(NodeBundle {
+        style: Style {
+            width: Val::Percent(100.),
+            height: Val::Percent(100.),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(12.),
+            ..default()
+        },
+        background_color: background.into(),
+        // keep the mouse off whatever is underneath
+        focus_policy: FocusPolicy::Block,
+        z_index: ZIndex::Global(10),
+        ..default()
+    });
+    menu.with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            title,
+            TextStyle {
+                font_size: 60.,
+                color: Color::WHITE,
+                ..default()
+            },
+        ));
+    });
+    menu
+}
+
+/// Adds the buttons, in order, and the hint line below them.
+pub fn spawn_buttons<'a>(
+    parent: &mut ChildBuilder,
+    labels: impl IntoIterator<Item = &'a str>,
+    font_size: f32,
+) {
+    for (index, label) in labels.into_iter().enumerate() {
+        parent
+            .spawn((
+                MenuButton(index),
+                ButtonBundle {
+                    style: Style {
+                        min_width: Val::Px(300.),
+                        padding: UiRect::axes(Val::Px(16.), Val::Px(font_size / 3.)),
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    background_color: BUTTON_COLOR.into(),
+                    ..default()
+                },
+            ))
+            .with_children(|button| {
+                button.spawn(TextBundle::from_section(
+                    label,
+                    TextStyle {
+                        font_size,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ));
+            });
+    }
+    parent.spawn((
+        MenuHint,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 20.,
+                color: Color::WHITE,
+                ..default()
+            },
+        ),
+    ));
+}
+
+/// Moves the focus with the keyboard, gamepad or mouse. Pipe it into the
+/// system that acts on the choice.
+pub fn navigate_menu(
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    buttons: Res<ButtonInput<GamepadButton>>,
+    menu_buttons: Query<&MenuButton>,
+    interactions: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    mut focus: ResMut<MenuFocus>,
+) -> Option<MenuInput> {
+    let pad = |button| gamepad_pressed(&gamepads, &buttons, button);
+    let count = menu_buttons.iter().count();
+    if count == 0 {
+        return None;
+    }
+
+    if keys.any_just_pressed([KeyCode::ArrowUp, KeyCode::KeyW]) || pad(GamepadButtonType::DPadUp) {
+        focus.0 = (focus.0 + count - 1) % count;
+    }
+    if keys.any_just_pressed([KeyCode::ArrowDown, KeyCode::KeyS])
+        || pad(GamepadButtonType::DPadDown)
+    {
+        focus.0 = (focus.0 + 1) % count;
+    }
+
+    for (interaction, button) in &interactions {
+        match interaction {
+            Interaction::Pressed => return Some(MenuInput::Pick(button.0)),
+            Interaction::Hovered => focus.0 = button.0,
+            Interaction::None => {}
+        }
+    }
+
+    let focused = focus.0.min(count - 1);
+    if keys.any_just_pressed([KeyCode::Enter, KeyCode::Space]) || pad(GamepadButtonType::South) {
+        return Some(MenuInput::Pick(focused));
+    }
+    if keys.any_just_pressed([KeyCode::ArrowLeft, KeyCode::KeyA])
+        || pad(GamepadButtonType::DPadLeft)
+    {
+        return Some(MenuInput::Adjust(focused, -1));
+    }
+    if keys.any_just_pressed([KeyCode::ArrowRight, KeyCode::KeyD])
+        || pad(GamepadButtonType::DPadRight)
+    {
+        return Some(MenuInput::Adjust(focused, 1));
+    }
+    if keys.just_pressed(KeyCode::Escape) || pad(GamepadButtonType::East) {
+        return Some(MenuInput::Back);
+    }
+    None
+}
+
+pub fn highlight_focus(
+    focus: Res<MenuFocus>,
+    mut buttons: Query<(&MenuButton, &mut BackgroundColor)>,
+) {
+    for (button, mut color) in &mut buttons {
+        *color = if button.0 == focus.0 {
+            FOCUSED_BUTTON_COLOR.into()
+        } else {
+            BUTTON_COLOR.into()
+        };
+    }
+}
+
+/// Shows `message` on the hint line of the open menu.
+pub fn show_hint(hint: &mut Query<&mut Text, With<MenuHint>>, message: String) {
+    if let Ok(mut text) = hint.get_single_mut() {
+        text.sections[0].value = message;
+    }
+}